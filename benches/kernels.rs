@@ -0,0 +1,164 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ml_distance::{bitset, distance, simd, similarity};
+
+const SIZES: [usize; 4] = [1_000, 10_000, 100_000, 1_000_000];
+
+fn vectors(n: usize) -> (Vec<f64>, Vec<f64>) {
+    let p = (0..n)
+        .map(|i| ((i * 31) % 97) as f64 / 97.0 + 0.01)
+        .collect();
+    let q = (0..n)
+        .map(|i| ((i * 17) % 89) as f64 / 89.0 + 0.01)
+        .collect();
+    (p, q)
+}
+
+// The previous implementations, walking the slices once per accumulator
+mod multi_pass {
+    pub fn sorensen<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+        p.iter()
+            .map(|&p| p.into())
+            .zip(q.iter().map(|&q| q.into()))
+            .map(|(p_i, q_i)| (p_i - q_i).abs())
+            .sum::<f64>()
+            / p.iter()
+                .map(|&p| p.into())
+                .zip(q.iter().map(|&q| q.into()))
+                .map(|(p_i, q_i)| p_i + q_i)
+                .sum::<f64>()
+    }
+
+    pub fn jaccard<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+        let pq = p
+            .iter()
+            .map(|&p| p.into())
+            .zip(q.iter().map(|&q| q.into()))
+            .map(|(p_i, q_i)| p_i * q_i)
+            .sum::<f64>();
+
+        1.0 - (pq
+            / (p.iter()
+                .map(|&p| p.into())
+                .map(|p_i| p_i * p_i)
+                .sum::<f64>()
+                + q.iter()
+                    .map(|&q| q.into())
+                    .map(|q_i| q_i * q_i)
+                    .sum::<f64>()
+                - pq))
+    }
+
+    pub fn cosine<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+        let numerator = p
+            .iter()
+            .map(|&p| p.into())
+            .zip(q.iter().map(|&q| q.into()))
+            .map(|(p_i, q_i)| p_i * q_i)
+            .sum::<f64>();
+        let denominator = p
+            .iter()
+            .map(|&p| p.into())
+            .map(|p_i| p_i * p_i)
+            .sum::<f64>()
+            .sqrt()
+            * q.iter()
+                .map(|&q| q.into())
+                .map(|q_i| q_i * q_i)
+                .sum::<f64>()
+                .sqrt();
+        numerator / denominator
+    }
+}
+
+type Measure = fn(&[f64], &[f64]) -> f64;
+
+fn bench_pair(c: &mut Criterion, name: &str, candidate: Measure, reference: Measure) {
+    let mut group = c.benchmark_group(name);
+
+    for n in SIZES {
+        let (p, q) = vectors(n);
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::new("candidate", n), &n, |b, _| {
+            b.iter(|| candidate(black_box(&p), black_box(&q)))
+        });
+        group.bench_with_input(BenchmarkId::new("reference", n), &n, |b, _| {
+            b.iter(|| reference(black_box(&p), black_box(&q)))
+        });
+    }
+
+    group.finish();
+}
+
+fn ratio_kernels(c: &mut Criterion) {
+    bench_pair(c, "sorensen", distance::sorensen, multi_pass::sorensen);
+    bench_pair(c, "jaccard", distance::jaccard, multi_pass::jaccard);
+    bench_pair(c, "cosine", similarity::cosine, multi_pass::cosine);
+}
+
+fn single_pass(c: &mut Criterion) {
+    let (p, q) = vectors(100_000);
+    let mut group = c.benchmark_group("single_pass");
+
+    let measures: [(&str, Measure); 9] = [
+        ("sorensen", distance::sorensen),
+        ("soergel", distance::soergel),
+        ("kulczynski", distance::kulczynski),
+        ("czekanowski", distance::czekanowski),
+        ("motyka", distance::motyka),
+        ("ruzicka", distance::ruzicka),
+        ("jaccard", distance::jaccard),
+        ("dice", distance::dice),
+        ("cosine", similarity::cosine),
+    ];
+    for (name, measure) in measures {
+        group.bench_function(name, |b| b.iter(|| measure(black_box(&p), black_box(&q))));
+    }
+
+    group.finish();
+}
+
+fn simd_kernels(c: &mut Criterion) {
+    bench_pair(c, "simd_euclidean", simd::euclidean, distance::euclidean);
+    bench_pair(
+        c,
+        "simd_inner_product",
+        simd::inner_product,
+        distance::inner_product,
+    );
+    bench_pair(c, "simd_cosine", simd::cosine, similarity::cosine);
+}
+
+// 1024-bit fingerprints, packed and as one f64 per bit
+fn bitset_kernels(c: &mut Criterion) {
+    let words = |seed: u64| -> Vec<u64> {
+        (0..16u64)
+            .map(|i| (i + seed).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+            .collect()
+    };
+    let unpack = |words: &[u64]| -> Vec<f64> {
+        (0..words.len() * 64)
+            .map(|i| ((words[i / 64] >> (i % 64)) & 1) as f64)
+            .collect()
+    };
+    let (p, q) = (words(1), words(2));
+    let (p_bits, q_bits) = (unpack(&p), unpack(&q));
+    let mut group = c.benchmark_group("bitset_tanimoto");
+
+    group.bench_function("packed", |b| {
+        b.iter(|| bitset::tanimoto(black_box(&p), black_box(&q)))
+    });
+    group.bench_function("unpacked", |b| {
+        b.iter(|| distance::tanimoto_bitvector(black_box(&p_bits), black_box(&q_bits)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    ratio_kernels,
+    single_pass,
+    simd_kernels,
+    bitset_kernels
+);
+criterion_main!(benches);
@@ -0,0 +1,395 @@
+use crate::knn::{sort_neighbours, Nearest, Neighbour};
+use crate::measure::Metric;
+use crate::pairwise::rows;
+
+#[derive(Debug, Clone)]
+struct Node {
+    start: usize,
+    end: usize,
+    radius: f64,
+    children: Option<(usize, usize)>,
+}
+
+// Ball tree over the rows of a flat row-major buffer. Every node bounds its
+// rows by a ball around their mean, so any `Metric` can be used to prune.
+#[derive(Debug, Clone)]
+pub struct BallTree<M> {
+    data: Vec<f64>,
+    dim: usize,
+    metric: M,
+    indices: Vec<usize>,
+    nodes: Vec<Node>,
+    centers: Vec<f64>,
+}
+
+const LEAF_SIZE: usize = 16;
+
+// Lower bound on the distance to the rows of a ball `radius` around a centre at
+// `distance`. The centres are rounded means, so the bound is loosened by a
+// relative margin of 1e-9, far above their rounding error, to keep rows lying
+// exactly at the pruning distance.
+fn gap(distance: f64, radius: f64) -> f64 {
+    distance - radius - 1e-9 * (distance + radius)
+}
+
+impl<M: Metric> BallTree<M> {
+    pub fn new(data: &[f64], dim: usize, metric: M) -> Self {
+        Self::with_leaf_size(data, dim, metric, LEAF_SIZE)
+    }
+
+    pub fn with_leaf_size(data: &[f64], dim: usize, metric: M, leaf_size: usize) -> Self {
+        assert!(
+            metric.is_metric(),
+            "{} is not a metric with these parameters",
+            metric.name()
+        );
+        let n = rows(data, dim).len();
+        let mut tree = BallTree {
+            data: data.to_vec(),
+            dim,
+            metric,
+            indices: (0..n).collect(),
+            nodes: Vec::new(),
+            centers: Vec::new(),
+        };
+        if n > 0 {
+            tree.build(0, n, leaf_size.max(1));
+        }
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    fn row(&self, index: usize) -> &[f64] {
+        &self.data[index * self.dim..(index + 1) * self.dim]
+    }
+
+    fn center(&self, node: usize) -> &[f64] {
+        &self.centers[node * self.dim..(node + 1) * self.dim]
+    }
+
+    fn build(&mut self, start: usize, end: usize, leaf_size: usize) -> usize {
+        let dim = self.dim;
+        let count = (end - start) as f64;
+        let mut center = vec![0.0; dim];
+        for &i in &self.indices[start..end] {
+            for (c, &v) in center.iter_mut().zip(self.row(i)) {
+                *c += v / count;
+            }
+        }
+        let radius = self.indices[start..end]
+            .iter()
+            .map(|&i| self.metric.distance(&center, self.row(i)))
+            .fold(0.0, f64::max);
+
+        let id = self.nodes.len();
+        self.centers.extend_from_slice(&center);
+        self.nodes.push(Node {
+            start,
+            end,
+            radius,
+            children: None,
+        });
+        if end - start <= leaf_size {
+            return id;
+        }
+
+        // Split at the median of the widest coordinate
+        let spread = |axis: usize| {
+            let values = self.indices[start..end]
+                .iter()
+                .map(|&i| self.data[i * dim + axis]);
+            let max = values.clone().fold(f64::NEG_INFINITY, f64::max);
+            max - values.fold(f64::INFINITY, f64::min)
+        };
+        let axis = (0..dim)
+            .map(|axis| (axis, spread(axis)))
+            .fold((0, f64::NEG_INFINITY), |best, (axis, spread)| {
+                if spread > best.1 {
+                    (axis, spread)
+                } else {
+                    best
+                }
+            })
+            .0;
+
+        let mid = start + (end - start) / 2;
+        let data = &self.data;
+        self.indices[start..end].select_nth_unstable_by(mid - start, |&a, &b| {
+            data[a * dim + axis].total_cmp(&data[b * dim + axis])
+        });
+
+        let left = self.build(start, mid, leaf_size);
+        let right = self.build(mid, end, leaf_size);
+        self.nodes[id].children = Some((left, right));
+        id
+    }
+
+    // Smallest distance any row of `node` can be from `query`
+    fn lower_bound(&self, node: usize, query: &[f64]) -> f64 {
+        let distance = self.metric.distance(query, self.center(node));
+        gap(distance, self.nodes[node].radius).max(0.0)
+    }
+
+    // The `k` nearest rows, closest first, ties broken by the lowest index
+    pub fn knn(&self, query: &[f64], k: usize) -> Vec<Neighbour<f64>> {
+        assert_eq!(query.len(), self.dim, "query length does not match dim");
+        let mut nearest = Nearest::new(k);
+        if k > 0 && !self.is_empty() {
+            self.search_knn(0, self.lower_bound(0, query), query, &mut nearest);
+        }
+        nearest.into_neighbours()
+    }
+
+    fn search_knn(&self, node: usize, gap: f64, query: &[f64], nearest: &mut Nearest<f64>) {
+        // Equal bounds are still visited so that ties resolve by index
+        if nearest.bound().is_some_and(|bound| gap > bound) {
+            return;
+        }
+
+        let Node {
+            start,
+            end,
+            children,
+            ..
+        } = self.nodes[node];
+        match children {
+            None => {
+                for &index in &self.indices[start..end] {
+                    nearest.push(index, self.metric.distance(query, self.row(index)));
+                }
+            }
+            Some((left, right)) => {
+                let (left_gap, right_gap) = (
+                    self.lower_bound(left, query),
+                    self.lower_bound(right, query),
+                );
+                if left_gap <= right_gap {
+                    self.search_knn(left, left_gap, query, nearest);
+                    self.search_knn(right, right_gap, query, nearest);
+                } else {
+                    self.search_knn(right, right_gap, query, nearest);
+                    self.search_knn(left, left_gap, query, nearest);
+                }
+            }
+        }
+    }
+
+    // Every row within `radius` (inclusive), closest first
+    pub fn radius(&self, query: &[f64], radius: f64) -> Vec<Neighbour<f64>> {
+        assert_eq!(query.len(), self.dim, "query length does not match dim");
+        let mut neighbours = Vec::new();
+        if !self.is_empty() {
+            self.search_radius(0, query, radius, &mut neighbours);
+        }
+        sort_neighbours(&mut neighbours);
+        neighbours
+    }
+
+    fn search_radius(
+        &self,
+        node: usize,
+        query: &[f64],
+        radius: f64,
+        out: &mut Vec<Neighbour<f64>>,
+    ) {
+        if self.lower_bound(node, query) > radius {
+            return;
+        }
+
+        let Node {
+            start,
+            end,
+            children,
+            ..
+        } = self.nodes[node];
+        match children {
+            None => {
+                for &index in &self.indices[start..end] {
+                    let score = self.metric.distance(query, self.row(index));
+                    if score <= radius {
+                        out.push(Neighbour { index, score });
+                    }
+                }
+            }
+            Some((left, right)) => {
+                self.search_radius(left, query, radius, out);
+                self.search_radius(right, query, radius, out);
+            }
+        }
+    }
+
+    // Every pair `(i, j, distance)` of a row `i` of `self` and a row `j` of
+    // `other` within `radius` (inclusive), sorted by `i` then `j`. Both trees
+    // are walked together so that whole pairs of distant nodes are skipped.
+    pub fn pairs_within(&self, other: &BallTree<M>, radius: f64) -> Vec<(usize, usize, f64)> {
+        assert_eq!(self.dim, other.dim, "trees have different dims");
+        let mut pairs = Vec::new();
+        if !self.is_empty() && !other.is_empty() {
+            self.dual(other, 0, 0, radius, false, &mut pairs);
+        }
+        pairs.sort_by_key(|&(i, j, _)| (i, j));
+        pairs
+    }
+
+    // Every pair `(i, j, distance)` of rows of `self` with `i < j` within
+    // `radius` (inclusive), sorted by `i` then `j`
+    pub fn self_pairs_within(&self, radius: f64) -> Vec<(usize, usize, f64)> {
+        let mut pairs = Vec::new();
+        if !self.is_empty() {
+            self.dual(self, 0, 0, radius, true, &mut pairs);
+        }
+        pairs.sort_by_key(|&(i, j, _)| (i, j));
+        pairs
+    }
+
+    fn dual(
+        &self,
+        other: &BallTree<M>,
+        a: usize,
+        b: usize,
+        radius: f64,
+        same: bool,
+        out: &mut Vec<(usize, usize, f64)>,
+    ) {
+        let (node_a, node_b) = (&self.nodes[a], &other.nodes[b]);
+        let distance = self.metric.distance(self.center(a), other.center(b));
+        if gap(distance, node_a.radius + node_b.radius) > radius {
+            return;
+        }
+
+        match (node_a.children, node_b.children) {
+            (None, None) => {
+                for &i in &self.indices[node_a.start..node_a.end] {
+                    for &j in &other.indices[node_b.start..node_b.end] {
+                        if same && i >= j && a == b {
+                            continue;
+                        }
+                        let score = self.metric.distance(self.row(i), other.row(j));
+                        if score <= radius {
+                            out.push(if same {
+                                (i.min(j), i.max(j), score)
+                            } else {
+                                (i, j, score)
+                            });
+                        }
+                    }
+                }
+            }
+            // A node paired with itself: visit each unordered pair of children once
+            (Some((left, right)), _) if same && a == b => {
+                self.dual(other, left, left, radius, same, out);
+                self.dual(other, left, right, radius, same, out);
+                self.dual(other, right, right, radius, same, out);
+            }
+            // Split the node holding more rows, or the only internal one
+            (Some((left, right)), None) => {
+                self.dual(other, left, b, radius, same, out);
+                self.dual(other, right, b, radius, same, out);
+            }
+            (Some((left, right)), Some(_))
+                if node_a.end - node_a.start >= node_b.end - node_b.start =>
+            {
+                self.dual(other, left, b, radius, same, out);
+                self.dual(other, right, b, radius, same, out);
+            }
+            (_, Some((left, right))) => {
+                self.dual(other, a, left, radius, same, out);
+                self.dual(other, a, right, radius, same, out);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::balltree::BallTree;
+    use crate::distance::{Euclidean, Hellinger, Lorentzian, Manhattan, Minkowski};
+    use crate::rng::samples;
+    use crate::{knn, Metric};
+
+    // Coarse non-negative values so that ties occur
+    fn dataset(n: usize, dim: usize, seed: u64) -> Vec<f64> {
+        samples(n * dim, seed, |rng| rng.below(20) as f64 / 4.0)
+    }
+
+    fn brute_pairs<M: Metric>(
+        metric: &M,
+        a: &[f64],
+        b: &[f64],
+        dim: usize,
+        radius: f64,
+        same: bool,
+    ) -> Vec<(usize, usize, f64)> {
+        let mut pairs = Vec::new();
+        for (i, p) in a.chunks(dim).enumerate() {
+            for (j, q) in b.chunks(dim).enumerate() {
+                let score = metric.distance(p, q);
+                if (!same || i < j) && score <= radius {
+                    pairs.push((i, j, score));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn matches_brute_force<M: Metric + Copy>(metric: M, r: f64) {
+        for dim in [2, 8, 32] {
+            let data = dataset(300, dim, dim as u64);
+            let queries = dataset(40, dim, 100 + dim as u64);
+            let measure = |p: &[f64], q: &[f64]| metric.distance(p, q);
+            let r = r * (dim as f64).sqrt();
+
+            let tree = BallTree::with_leaf_size(&data, dim, metric, 5);
+            for query in queries.chunks(dim) {
+                for k in [1, 4, 15] {
+                    assert_eq!(tree.knn(query, k), knn::knn(query, &data, dim, k, measure));
+                }
+                assert_eq!(
+                    tree.radius(query, r),
+                    knn::radius(query, &data, dim, r, measure)
+                );
+            }
+
+            let other = BallTree::with_leaf_size(&queries, dim, metric, 3);
+            assert_eq!(
+                other.pairs_within(&tree, r),
+                brute_pairs(&metric, &queries, &data, dim, r, false)
+            );
+            assert_eq!(
+                tree.self_pairs_within(r),
+                brute_pairs(&metric, &data, &data, dim, r, true)
+            );
+        }
+    }
+
+    #[test]
+    fn metrics_match_brute_force() {
+        matches_brute_force(Euclidean, 1.0);
+        matches_brute_force(Manhattan, 2.0);
+        matches_brute_force(Minkowski { d: 3.0 }, 1.0);
+        matches_brute_force(Lorentzian, 1.0);
+        matches_brute_force(Hellinger, 0.5);
+    }
+
+    #[test]
+    fn edge_cases() {
+        let data = dataset(20, 3, 1);
+        let tree = BallTree::new(&data, 3, Euclidean);
+        assert_eq!(tree.len(), 20);
+        assert!(tree.knn(&[1.0, 1.0, 1.0], 0).is_empty());
+        assert_eq!(tree.knn(&[1.0, 1.0, 1.0], 50).len(), 20);
+        assert_eq!(tree.self_pairs_within(f64::INFINITY).len(), 190);
+
+        let empty = BallTree::new(&[], 3, Euclidean);
+        assert!(empty.knn(&[0.0, 0.0, 0.0], 1).is_empty());
+        assert!(empty.radius(&[0.0, 0.0, 0.0], 1.0).is_empty());
+        assert!(empty.pairs_within(&tree, 1.0).is_empty());
+    }
+}
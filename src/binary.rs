@@ -0,0 +1,283 @@
+// Dissimilarities between binary vectors, derived from their 2x2 contingency
+// table. For 0/1 vectors `hamming`, `jaccard`, `dice` and `kulczynski` agree
+// with the measures of the same name in `distance`; the others have no
+// continuous counterpart. A denominator of zero gives NaN or infinity, as in
+// the rest of the crate. The `try_*` versions return a length mismatch as an
+// error, and a zero denominator as `Error::DivisionByZero`.
+//
+// For packed bitsets, `bitset::hamming` and `bitset::tanimoto` (the Jaccard
+// dissimilarity) give the same results as `from_words` with all the bits, with
+// a hardware popcount and without counting the full table.
+
+use crate::error::{check_result, Error, Result};
+
+// Counts of the positions where `p` and `q` are (true, true), (true, false),
+// (false, true) and (false, false)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Contingency {
+    pub a: usize,
+    pub b: usize,
+    pub c: usize,
+    pub d: usize,
+}
+
+impl Contingency {
+    pub fn from_bools(p: &[bool], q: &[bool]) -> Self {
+        assert_eq!(p.len(), q.len(), "vectors have different lengths");
+        let mut table = Contingency::default();
+        for (&p_i, &q_i) in p.iter().zip(q) {
+            match (p_i, q_i) {
+                (true, true) => table.a += 1,
+                (true, false) => table.b += 1,
+                (false, true) => table.c += 1,
+                (false, false) => table.d += 1,
+            }
+        }
+        table
+    }
+
+    pub fn try_from_bools(p: &[bool], q: &[bool]) -> Result<Self> {
+        check_lengths(p.len(), q.len())?;
+        Ok(Self::from_bools(p, q))
+    }
+
+    // Bitsets packed into words, bit `i` of the vector being bit `i % 64` of
+    // word `i / 64`. Only the first `bits` bits are counted.
+    pub fn from_words(p: &[u64], q: &[u64], bits: usize) -> Self {
+        assert_eq!(p.len(), q.len(), "bitsets have different lengths");
+        assert!(bits <= p.len() * 64, "more bits than the words hold");
+        let (mut a, mut b, mut c) = (0, 0, 0);
+        for (word, (&p_w, &q_w)) in p.iter().zip(q).enumerate().take(bits.div_ceil(64)) {
+            let remaining = bits - word * 64;
+            let mask = if remaining >= 64 {
+                u64::MAX
+            } else {
+                (1 << remaining) - 1
+            };
+            let (p_w, q_w) = (p_w & mask, q_w & mask);
+            a += (p_w & q_w).count_ones() as usize;
+            b += (p_w & !q_w).count_ones() as usize;
+            c += (!p_w & q_w).count_ones() as usize;
+        }
+        Contingency {
+            a,
+            b,
+            c,
+            d: bits - a - b - c,
+        }
+    }
+
+    // Asking for more bits than the words hold is reported as a mismatch
+    // between `bits` and the number of bits in the words
+    pub fn try_from_words(p: &[u64], q: &[u64], bits: usize) -> Result<Self> {
+        check_lengths(p.len(), q.len())?;
+        if bits > p.len() * 64 {
+            return Err(Error::LengthMismatch {
+                p: bits,
+                q: p.len() * 64,
+            });
+        }
+        Ok(Self::from_words(p, q, bits))
+    }
+
+    pub fn len(&self) -> usize {
+        self.a + self.b + self.c + self.d
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn mismatches(&self) -> f64 {
+        (self.b + self.c) as f64
+    }
+
+    // Number of differing positions
+    pub fn hamming(&self) -> f64 {
+        self.mismatches()
+    }
+
+    pub fn jaccard(&self) -> f64 {
+        self.mismatches() / (self.a + self.b + self.c) as f64
+    }
+
+    pub fn dice(&self) -> f64 {
+        self.mismatches() / (2 * self.a + self.b + self.c) as f64
+    }
+
+    pub fn russell_rao(&self) -> f64 {
+        (self.len() - self.a) as f64 / self.len() as f64
+    }
+
+    pub fn sokal_michener(&self) -> f64 {
+        self.mismatches() / self.len() as f64
+    }
+
+    pub fn rogers_tanimoto(&self) -> f64 {
+        let r = 2.0 * self.mismatches();
+        r / ((self.a + self.d) as f64 + r)
+    }
+
+    pub fn yule(&self) -> f64 {
+        let (ad, bc) = ((self.a * self.d) as f64, (self.b * self.c) as f64);
+        2.0 * bc / (ad + bc)
+    }
+
+    pub fn kulczynski(&self) -> f64 {
+        self.mismatches() / self.a as f64
+    }
+
+    pub fn sokal_sneath(&self) -> f64 {
+        let r = 2.0 * self.mismatches();
+        r / (self.a as f64 + r)
+    }
+}
+
+fn check_lengths(p: usize, q: usize) -> Result<()> {
+    if p != q {
+        return Err(Error::LengthMismatch { p, q });
+    }
+    Ok(())
+}
+
+// Generates the slice versions of the `Contingency` measures, and their checked
+// counterparts
+macro_rules! binary_measures {
+    ($($name:ident, $try_name:ident);* $(;)?) => {
+        $(
+            pub fn $name(p: &[bool], q: &[bool]) -> f64 {
+                Contingency::from_bools(p, q).$name()
+            }
+
+            #[doc = concat!("Checked version of [`", stringify!($name), "`].")]
+            pub fn $try_name(p: &[bool], q: &[bool]) -> Result<f64> {
+                check_result(Contingency::try_from_bools(p, q)?.$name())
+            }
+        )*
+    };
+}
+
+binary_measures! {
+    hamming, try_hamming;
+    jaccard, try_jaccard;
+    dice, try_dice;
+    russell_rao, try_russell_rao;
+    sokal_michener, try_sokal_michener;
+    rogers_tanimoto, try_rogers_tanimoto;
+    yule, try_yule;
+    kulczynski, try_kulczynski;
+    sokal_sneath, try_sokal_sneath;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binary::{self, Contingency};
+    use crate::{distance, Error};
+
+    const P: [bool; 8] = [true, true, false, true, false, false, true, false];
+    const Q: [bool; 8] = [true, false, true, true, false, false, false, false];
+
+    fn pack(bits: &[bool]) -> Vec<u64> {
+        let mut words = vec![0; bits.len().div_ceil(64)];
+        for (i, &bit) in bits.iter().enumerate() {
+            words[i / 64] |= u64::from(bit) << (i % 64);
+        }
+        words
+    }
+
+    #[test]
+    fn contingency() {
+        let table = Contingency::from_bools(&P, &Q);
+        assert_eq!(
+            table,
+            Contingency {
+                a: 2,
+                b: 2,
+                c: 1,
+                d: 3
+            }
+        );
+        assert_eq!(table.len(), 8);
+        assert_eq!(Contingency::from_words(&pack(&P), &pack(&Q), 8), table);
+    }
+
+    #[test]
+    fn words_beyond_length_are_ignored() {
+        let p: Vec<bool> = (0..150).map(|i| i % 3 == 0).collect();
+        let q: Vec<bool> = (0..150).map(|i| i % 5 == 0).collect();
+        let (mut p_words, mut q_words) = (pack(&p), pack(&q));
+        p_words[2] |= u64::MAX << 22;
+        q_words[2] |= 1 << 40;
+        assert_eq!(
+            Contingency::from_words(&p_words, &q_words, 150),
+            Contingency::from_bools(&p, &q)
+        );
+        assert_eq!(
+            Contingency::from_words(&p_words[..2], &q_words[..2], 128),
+            Contingency::from_bools(&p[..128], &q[..128])
+        );
+    }
+
+    #[test]
+    fn measures() {
+        assert_eq!(binary::hamming(&P, &Q), 3.0);
+        assert_relative_eq!(binary::jaccard(&P, &Q), 0.6);
+        assert_relative_eq!(binary::dice(&P, &Q), 3.0 / 7.0);
+        assert_relative_eq!(binary::russell_rao(&P, &Q), 0.75);
+        assert_relative_eq!(binary::sokal_michener(&P, &Q), 0.375);
+        assert_relative_eq!(binary::rogers_tanimoto(&P, &Q), 6.0 / 11.0);
+        assert_relative_eq!(binary::yule(&P, &Q), 0.5);
+        assert_relative_eq!(binary::kulczynski(&P, &Q), 1.5);
+        assert_relative_eq!(binary::sokal_sneath(&P, &Q), 0.75);
+    }
+
+    #[test]
+    fn continuous_counterparts() {
+        let (p, q) = (P.map(f64::from), Q.map(f64::from));
+        assert_eq!(binary::hamming(&P, &Q), distance::hamming(&p, &q));
+        assert_relative_eq!(binary::jaccard(&P, &Q), distance::jaccard(&p, &q));
+        assert_relative_eq!(binary::dice(&P, &Q), distance::dice(&p, &q));
+        assert_relative_eq!(binary::kulczynski(&P, &Q), distance::kulczynski(&p, &q));
+    }
+
+    #[test]
+    fn undefined() {
+        let zeros = [false; 4];
+        assert!(binary::jaccard(&zeros, &zeros).is_nan());
+        assert!(binary::yule(&zeros, &zeros).is_nan());
+        assert_eq!(binary::sokal_michener(&zeros, &zeros), 0.0);
+    }
+
+    #[test]
+    fn checked() {
+        assert_eq!(
+            Contingency::try_from_bools(&P, &Q),
+            Ok(Contingency::from_bools(&P, &Q))
+        );
+        assert_eq!(
+            Contingency::try_from_bools(&P, &Q[..4]),
+            Err(Error::LengthMismatch { p: 8, q: 4 })
+        );
+        assert_eq!(
+            Contingency::try_from_words(&[1, 2], &[1], 64),
+            Err(Error::LengthMismatch { p: 2, q: 1 })
+        );
+        assert_eq!(
+            Contingency::try_from_words(&[1], &[3], 65),
+            Err(Error::LengthMismatch { p: 65, q: 64 })
+        );
+        assert_eq!(binary::try_dice(&P, &Q), Ok(binary::dice(&P, &Q)));
+        assert_eq!(
+            binary::try_jaccard(&P, &Q[..4]),
+            Err(Error::LengthMismatch { p: 8, q: 4 })
+        );
+        let zeros = [false; 4];
+        assert_eq!(binary::try_yule(&zeros, &zeros), Err(Error::DivisionByZero));
+    }
+
+    #[test]
+    #[should_panic(expected = "different lengths")]
+    fn length_mismatch() {
+        binary::jaccard(&P, &Q[..4]);
+    }
+}
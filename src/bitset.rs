@@ -0,0 +1,276 @@
+// Hamming and Tanimoto over bitsets packed into `u64` words, for binary
+// fingerprints. Each word is reduced with a popcount, which on x86_64 uses the
+// POPCNT instruction when the CPU has it (picked at runtime, like `simd`).
+// Collections of fingerprints are flat buffers of `words` words per row.
+//
+// `hamming` and `tanimoto` are the fast paths of `binary::Contingency::hamming`
+// and `jaccard` on the same words: the Tanimoto distance on bits is the Jaccard
+// dissimilarity, `1 - |p & q| / |p | q|`, also equal to
+// `distance::tanimoto_bitvector` on the unpacked bits. Two empty bitsets give
+// NaN. For the other binary measures, count a `Contingency::from_words`.
+
+use crate::error::{check_result, Error, Result};
+use crate::knn::{Nearest, Neighbour};
+use crate::pairwise::rows;
+
+mod portable {
+    // `#[inline(always)]` lets the POPCNT wrappers below compile these bodies
+    // with the instruction enabled
+    #[inline(always)]
+    pub(super) fn hamming(p: &[u64], q: &[u64]) -> f64 {
+        let differing: u32 = p
+            .iter()
+            .zip(q)
+            .map(|(p_w, q_w)| (p_w ^ q_w).count_ones())
+            .sum();
+        f64::from(differing)
+    }
+
+    #[inline(always)]
+    pub(super) fn tanimoto(p: &[u64], q: &[u64]) -> f64 {
+        let (common, union) = p
+            .iter()
+            .zip(q)
+            .fold((0u32, 0u32), |(common, union), (p_w, q_w)| {
+                (
+                    common + (p_w & q_w).count_ones(),
+                    union + (p_w | q_w).count_ones(),
+                )
+            });
+        f64::from(union - common) / f64::from(union)
+    }
+
+    #[inline(always)]
+    pub(super) fn knn(
+        query: &[u64],
+        data: &[u64],
+        words: usize,
+        k: usize,
+        measure: impl Fn(&[u64], &[u64]) -> f64,
+    ) -> Vec<super::Neighbour<f64>> {
+        let mut nearest = super::Nearest::new(k);
+        for (index, row) in super::rows(data, words).enumerate() {
+            nearest.push(index, measure(query, row));
+        }
+        nearest.into_neighbours()
+    }
+
+    #[inline(always)]
+    pub(super) fn knn_hamming(
+        query: &[u64],
+        data: &[u64],
+        words: usize,
+        k: usize,
+    ) -> Vec<super::Neighbour<f64>> {
+        knn(query, data, words, k, hamming)
+    }
+
+    #[inline(always)]
+    pub(super) fn knn_tanimoto(
+        query: &[u64],
+        data: &[u64],
+        words: usize,
+        k: usize,
+    ) -> Vec<super::Neighbour<f64>> {
+        knn(query, data, words, k, tanimoto)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod popcnt {
+    use super::{portable, Neighbour};
+
+    #[target_feature(enable = "popcnt")]
+    pub(super) unsafe fn hamming(p: &[u64], q: &[u64]) -> f64 {
+        portable::hamming(p, q)
+    }
+
+    #[target_feature(enable = "popcnt")]
+    pub(super) unsafe fn tanimoto(p: &[u64], q: &[u64]) -> f64 {
+        portable::tanimoto(p, q)
+    }
+
+    #[target_feature(enable = "popcnt")]
+    pub(super) unsafe fn knn_hamming(
+        query: &[u64],
+        data: &[u64],
+        words: usize,
+        k: usize,
+    ) -> Vec<Neighbour<f64>> {
+        portable::knn_hamming(query, data, words, k)
+    }
+
+    #[target_feature(enable = "popcnt")]
+    pub(super) unsafe fn knn_tanimoto(
+        query: &[u64],
+        data: &[u64],
+        words: usize,
+        k: usize,
+    ) -> Vec<Neighbour<f64>> {
+        portable::knn_tanimoto(query, data, words, k)
+    }
+}
+
+macro_rules! dispatch {
+    ($kernel:ident($($arg:expr),*)) => {{
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("popcnt") {
+                unsafe { popcnt::$kernel($($arg),*) }
+            } else {
+                portable::$kernel($($arg),*)
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            portable::$kernel($($arg),*)
+        }
+    }};
+}
+
+// Number of differing bits
+pub fn hamming(p: &[u64], q: &[u64]) -> f64 {
+    assert_eq!(p.len(), q.len(), "bitsets have different lengths");
+    dispatch!(hamming(p, q))
+}
+
+pub fn tanimoto(p: &[u64], q: &[u64]) -> f64 {
+    assert_eq!(p.len(), q.len(), "bitsets have different lengths");
+    dispatch!(tanimoto(p, q))
+}
+
+fn check_words(p: &[u64], q: &[u64]) -> Result<()> {
+    if p.len() != q.len() {
+        return Err(Error::LengthMismatch {
+            p: p.len(),
+            q: q.len(),
+        });
+    }
+    Ok(())
+}
+
+pub fn try_hamming(p: &[u64], q: &[u64]) -> Result<f64> {
+    check_words(p, q)?;
+    Ok(dispatch!(hamming(p, q)))
+}
+
+// Two empty bitsets are `Error::DivisionByZero`
+pub fn try_tanimoto(p: &[u64], q: &[u64]) -> Result<f64> {
+    check_words(p, q)?;
+    check_result(dispatch!(tanimoto(p, q)))
+}
+
+// The `k` rows of `data` with the fewest bits differing from `query`, closest
+// first. Ties are broken by the lowest index, as in `knn::knn`.
+pub fn knn_hamming(query: &[u64], data: &[u64], words: usize, k: usize) -> Vec<Neighbour<f64>> {
+    assert_eq!(query.len(), words, "query length does not match words");
+    dispatch!(knn_hamming(query, data, words, k))
+}
+
+// The `k` rows of `data` closest to `query` in Tanimoto distance
+pub fn knn_tanimoto(query: &[u64], data: &[u64], words: usize, k: usize) -> Vec<Neighbour<f64>> {
+    assert_eq!(query.len(), words, "query length does not match words");
+    dispatch!(knn_tanimoto(query, data, words, k))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binary::Contingency;
+    use crate::rng::samples;
+    use crate::{bitset, distance, knn, Error};
+
+    // 16 words, the size of a 1024-bit fingerprint
+    const WORDS: usize = 16;
+
+    // Sparse-ish words, like hashed substructure fingerprints
+    fn fingerprints(n: usize, seed: u64) -> Vec<u64> {
+        samples(n * WORDS, seed, |rng| {
+            let word = rng.next_u64();
+            word & (word >> 7) & (word >> 29)
+        })
+    }
+
+    fn unpack(words: &[u64]) -> Vec<f64> {
+        (0..words.len() * 64)
+            .map(|i| ((words[i / 64] >> (i % 64)) & 1) as f64)
+            .collect()
+    }
+
+    #[test]
+    fn matches_unpacked() {
+        let data = fingerprints(6, 11);
+        for p in data.chunks_exact(WORDS) {
+            for q in data.chunks_exact(WORDS) {
+                let (p_bits, q_bits) = (unpack(p), unpack(q));
+                assert_eq!(bitset::hamming(p, q), distance::hamming(&p_bits, &q_bits));
+                assert_eq!(
+                    bitset::tanimoto(p, q),
+                    distance::tanimoto_bitvector(&p_bits, &q_bits)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_contingency() {
+        let data = fingerprints(6, 13);
+        for p in data.chunks_exact(WORDS) {
+            for q in data.chunks_exact(WORDS) {
+                let table = Contingency::from_words(p, q, WORDS * 64);
+                assert_eq!(bitset::hamming(p, q), table.hamming());
+                assert_eq!(bitset::tanimoto(p, q), table.jaccard());
+            }
+        }
+    }
+
+    #[test]
+    fn small_values() {
+        assert_eq!(bitset::hamming(&[0b1011, 1], &[0b0110, 0]), 4.0);
+        assert_relative_eq!(bitset::tanimoto(&[0b1011], &[0b0110]), 0.75);
+        assert_eq!(bitset::tanimoto(&[u64::MAX], &[u64::MAX]), 0.0);
+        assert!(bitset::tanimoto(&[0], &[0]).is_nan());
+    }
+
+    #[test]
+    fn knn_matches_brute_force() {
+        let data = fingerprints(300, 5);
+        let queries = fingerprints(5, 99);
+        for query in queries.chunks_exact(WORDS) {
+            let expected = knn::knn(query, &data, WORDS, 10, bitset::hamming);
+            assert_eq!(bitset::knn_hamming(query, &data, WORDS, 10), expected);
+
+            let expected = knn::knn(query, &data, WORDS, 10, bitset::tanimoto);
+            assert_eq!(bitset::knn_tanimoto(query, &data, WORDS, 10), expected);
+        }
+    }
+
+    #[test]
+    fn knn_finds_itself() {
+        let data = fingerprints(50, 3);
+        let query = &data[17 * WORDS..18 * WORDS];
+        let result = bitset::knn_tanimoto(query, &data, WORDS, 1);
+        assert_eq!(result[0].index, 17);
+        assert_eq!(result[0].score, 0.0);
+    }
+
+    #[test]
+    fn checked() {
+        assert_eq!(bitset::try_hamming(&[0b1011], &[0b0110]), Ok(3.0));
+        assert_eq!(bitset::try_tanimoto(&[0b1011], &[0b0110]), Ok(0.75));
+        assert_eq!(
+            bitset::try_hamming(&[1, 2], &[1]),
+            Err(Error::LengthMismatch { p: 2, q: 1 })
+        );
+        assert_eq!(
+            bitset::try_tanimoto(&[1], &[1, 2]),
+            Err(Error::LengthMismatch { p: 1, q: 2 })
+        );
+        assert_eq!(bitset::try_tanimoto(&[0], &[0]), Err(Error::DivisionByZero));
+    }
+
+    #[test]
+    #[should_panic(expected = "different lengths")]
+    fn length_mismatch() {
+        bitset::hamming(&[1, 2], &[1]);
+    }
+}
@@ -0,0 +1,286 @@
+// Covariance estimation and the dense linear algebra `distance::mahalanobis`
+// needs. Matrices are `dim * dim` row-major buffers.
+
+use crate::error::{Error, Result};
+use crate::pairwise::rows;
+
+fn mean(data: &[f64], dim: usize) -> Vec<f64> {
+    let n = rows(data, dim).len() as f64;
+    let mut mean = vec![0.0; dim];
+    for row in rows(data, dim) {
+        for (m, &v) in mean.iter_mut().zip(row) {
+            *m += v / n;
+        }
+    }
+    mean
+}
+
+// `X^T X / divisor` of the centered rows
+fn scatter(data: &[f64], dim: usize, divisor: f64) -> Vec<f64> {
+    let mean = mean(data, dim);
+    let mut matrix = vec![0.0; dim * dim];
+    for row in rows(data, dim) {
+        let centered: Vec<f64> = row.iter().zip(&mean).map(|(v, m)| v - m).collect();
+        for i in 0..dim {
+            for j in 0..=i {
+                matrix[i * dim + j] += centered[i] * centered[j] / divisor;
+            }
+        }
+    }
+    for i in 0..dim {
+        for j in 0..i {
+            matrix[j * dim + i] = matrix[i * dim + j];
+        }
+    }
+    matrix
+}
+
+// Unbiased sample covariance of the rows of `data` (divides by `n - 1`)
+pub fn empirical(data: &[f64], dim: usize) -> Result<Vec<f64>> {
+    let n = rows(data, dim).len();
+    if n < 2 {
+        return Err(Error::EmptyInput);
+    }
+    Ok(scatter(data, dim, (n - 1) as f64))
+}
+
+// Unbiased per-feature variances, the diagonal of `empirical`. A constant
+// feature has a variance of zero.
+pub fn variances(data: &[f64], dim: usize) -> Result<Vec<f64>> {
+    let n = rows(data, dim).len();
+    if n < 2 {
+        return Err(Error::EmptyInput);
+    }
+    let mean = mean(data, dim);
+    let mut variances = vec![0.0; dim];
+    for row in rows(data, dim) {
+        for ((variance, &v), m) in variances.iter_mut().zip(row).zip(&mean) {
+            *variance += (v - m).powi(2) / (n - 1) as f64;
+        }
+    }
+    Ok(variances)
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_unstable_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+// Robust per-feature variances: the squared median absolute deviation, scaled
+// by 1.4826 so that it estimates the standard deviation of normal data. It is
+// zero when more than half of a feature's values are equal.
+pub fn mad_variances(data: &[f64], dim: usize) -> Result<Vec<f64>> {
+    let n = rows(data, dim).len();
+    if n == 0 {
+        return Err(Error::EmptyInput);
+    }
+    Ok((0..dim)
+        .map(|j| {
+            let mut column: Vec<f64> = rows(data, dim).map(|row| row[j]).collect();
+            let center = median(&mut column);
+            let mut deviations: Vec<f64> = column.iter().map(|v| (v - center).abs()).collect();
+            (1.4826 * median(&mut deviations)).powi(2)
+        })
+        .collect())
+}
+
+// Ledoit-Wolf shrinkage of the maximum-likelihood covariance towards a scaled
+// identity, `(1 - s) * S + s * mu * I`. Returns the estimate and `s`. Unlike
+// `empirical`, the result stays well conditioned when there are few rows.
+pub fn ledoit_wolf(data: &[f64], dim: usize) -> Result<(Vec<f64>, f64)> {
+    let n = rows(data, dim).len();
+    if n == 0 {
+        return Err(Error::EmptyInput);
+    }
+    let n_f = n as f64;
+    let mut covariance = scatter(data, dim, n_f);
+    let mu = (0..dim).map(|i| covariance[i * dim + i]).sum::<f64>() / dim as f64;
+
+    // Squared distance of S to the target, and the variance of its entries
+    let delta = (0..dim * dim)
+        .map(|k| {
+            let target = if k % (dim + 1) == 0 { mu } else { 0.0 };
+            (covariance[k] - target).powi(2)
+        })
+        .sum::<f64>()
+        / dim as f64;
+
+    let mean = mean(data, dim);
+    let mut beta = 0.0;
+    for row in rows(data, dim) {
+        let centered: Vec<f64> = row.iter().zip(&mean).map(|(v, m)| v - m).collect();
+        for i in 0..dim {
+            for j in 0..dim {
+                let term = centered[i] * centered[j] - covariance[i * dim + j];
+                beta += term * term;
+            }
+        }
+    }
+    beta /= n_f * n_f * dim as f64;
+
+    let shrinkage = if delta > 0.0 {
+        beta.min(delta) / delta
+    } else {
+        0.0
+    };
+    for (k, value) in covariance.iter_mut().enumerate() {
+        *value *= 1.0 - shrinkage;
+        if k % (dim + 1) == 0 {
+            *value += shrinkage * mu;
+        }
+    }
+    Ok((covariance, shrinkage))
+}
+
+// Lower-triangular `L` with `matrix = L L^T`
+pub fn cholesky(matrix: &[f64], dim: usize) -> Result<Vec<f64>> {
+    assert_eq!(matrix.len(), dim * dim, "matrix is not dim * dim");
+    let mut factor = vec![0.0; dim * dim];
+    for i in 0..dim {
+        for j in 0..=i {
+            let sum: f64 = (0..j)
+                .map(|k| factor[i * dim + k] * factor[j * dim + k])
+                .sum();
+            let value = matrix[i * dim + j] - sum;
+            if i == j {
+                if value <= 0.0 || value.is_nan() {
+                    return Err(Error::NotPositiveDefinite);
+                }
+                factor[i * dim + i] = value.sqrt();
+            } else {
+                factor[i * dim + j] = value / factor[j * dim + j];
+            }
+        }
+    }
+    Ok(factor)
+}
+
+// Solves `L y = b` for lower-triangular `L`, in place
+fn forward_substitution(factor: &[f64], dim: usize, b: &mut [f64]) {
+    for i in 0..dim {
+        let sum: f64 = (0..i).map(|k| factor[i * dim + k] * b[k]).sum();
+        b[i] = (b[i] - sum) / factor[i * dim + i];
+    }
+}
+
+// Inverse of a symmetric positive definite matrix, through its Cholesky factor
+pub fn inverse(matrix: &[f64], dim: usize) -> Result<Vec<f64>> {
+    let factor = cholesky(matrix, dim)?;
+    // inv(A) = inv(L)^T inv(L), built from the columns of inv(L)
+    let mut inverse_factor = vec![0.0; dim * dim];
+    for column in 0..dim {
+        let mut unit = vec![0.0; dim];
+        unit[column] = 1.0;
+        forward_substitution(&factor, dim, &mut unit);
+        for (row, value) in unit.into_iter().enumerate() {
+            inverse_factor[row * dim + column] = value;
+        }
+    }
+
+    let mut inverse = vec![0.0; dim * dim];
+    for i in 0..dim {
+        for j in 0..dim {
+            inverse[i * dim + j] = (0..dim)
+                .map(|k| inverse_factor[k * dim + i] * inverse_factor[k * dim + j])
+                .sum();
+        }
+    }
+    Ok(inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{covariance, Error};
+
+    const DATA: [f64; 15] = [
+        2.0, 0.5, 1.0, //
+        3.0, 1.5, 0.0, //
+        4.0, 1.0, 2.0, //
+        1.0, 0.0, 1.5, //
+        5.0, 2.5, 0.5, //
+    ];
+
+    #[test]
+    fn empirical() {
+        let covariance = covariance::empirical(&DATA, 3).unwrap();
+        let expected = [
+            2.5, 1.375, -0.25, 1.375, 0.925, -0.4375, -0.25, -0.4375, 0.625,
+        ];
+        for (value, expected) in covariance.iter().zip(expected) {
+            assert_relative_eq!(*value, expected, epsilon = 1e-12);
+        }
+        assert_eq!(covariance::empirical(&DATA[..3], 3), Err(Error::EmptyInput));
+    }
+
+    #[test]
+    fn variances() {
+        let variances = covariance::variances(&DATA, 3).unwrap();
+        assert_relative_eq!(variances[0], 2.5, epsilon = 1e-12);
+        assert_relative_eq!(variances[1], 0.925, epsilon = 1e-12);
+        assert_relative_eq!(variances[2], 0.625, epsilon = 1e-12);
+
+        // The outlier inflates the variance but not the MAD
+        let data = [1.0, 2.0, 3.0, 4.0, 100.0];
+        assert_relative_eq!(covariance::variances(&data, 1).unwrap()[0], 1902.5);
+        let mad = covariance::mad_variances(&data, 1).unwrap();
+        assert_relative_eq!(mad[0], 1.4826 * 1.4826, epsilon = 1e-12);
+
+        let mad = covariance::mad_variances(&DATA, 3).unwrap();
+        assert_relative_eq!(mad[0], 1.4826 * 1.4826, epsilon = 1e-12);
+        assert_relative_eq!(mad[1], (1.4826 * 0.5) * (1.4826 * 0.5), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn ledoit_wolf() {
+        let (covariance, shrinkage) = covariance::ledoit_wolf(&DATA, 3).unwrap();
+        assert_relative_eq!(shrinkage, 0.374_912_202_6, epsilon = 1e-9);
+        let expected = [
+            1.655_080_773_6,
+            0.687_596_577_1,
+            -0.125_017_559_5,
+            0.687_596_577_1,
+            0.867_470_148_9,
+            -0.218_780_729_1,
+            -0.125_017_559_5,
+            -0.218_780_729_1,
+            0.717_449_077_5,
+        ];
+        for (value, expected) in covariance.iter().zip(expected) {
+            assert_relative_eq!(*value, expected, epsilon = 1e-9);
+        }
+
+        // A single row is degenerate: no shrinkage and a zero matrix
+        let (covariance, shrinkage) = covariance::ledoit_wolf(&DATA[..3], 3).unwrap();
+        assert_eq!(shrinkage, 0.0);
+        assert!(covariance.iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn cholesky_and_inverse() {
+        let matrix = [4.0, 2.0, 0.4, 2.0, 5.0, 1.0, 0.4, 1.0, 3.0];
+        let factor = covariance::cholesky(&matrix, 3).unwrap();
+        assert_relative_eq!(factor[0], 2.0);
+        assert_relative_eq!(factor[3], 1.0);
+        assert_relative_eq!(factor[4], 2.0);
+        assert_eq!(factor[1], 0.0);
+
+        let inverse = covariance::inverse(&matrix, 3).unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                let product: f64 = (0..3).map(|k| matrix[i * 3 + k] * inverse[k * 3 + j]).sum();
+                assert_relative_eq!(product, if i == j { 1.0 } else { 0.0 }, epsilon = 1e-12);
+            }
+        }
+
+        let singular = [1.0, 2.0, 2.0, 4.0];
+        assert_eq!(
+            covariance::cholesky(&singular, 2),
+            Err(Error::NotPositiveDefinite)
+        );
+    }
+}
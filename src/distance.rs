@@ -1,413 +1,813 @@
-pub fn euclidean<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+use crate::element::{lit, Element};
+use crate::error::{check_distributions, check_overflow, check_pair, check_result, fallible};
+use crate::measure::measures;
+use num_traits::{Float, Zero};
+
+// Walks both slices once, converting every element a single time
+pub(crate) fn fold_pairs<T, A, F>(p: &[T], q: &[T], init: A, f: F) -> A
+where
+    T: Element,
+    F: FnMut(A, (T::Float, T::Float)) -> A,
+{
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| (p_i - q_i).powf(2.0))
-        .sum::<f64>()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .fold(init, f)
+}
+
+// `(p . q, p . p, q . q)` in a single pass
+pub(crate) fn dot_products<T: Element>(p: &[T], q: &[T]) -> (T::Float, T::Float, T::Float) {
+    fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero(), T::Float::zero()),
+        |(pq, pp, qq), (p_i, q_i)| (pq + p_i * q_i, pp + p_i * p_i, qq + q_i * q_i),
+    )
+}
+
+// `x * ln(y)`, with the `0 * ln(0) = 0` convention of information theory so
+// that distributions with empty bins stay comparable
+fn x_ln<F: Float>(x: F, y: F) -> F {
+    if x.is_zero() {
+        F::zero()
+    } else {
+        x * y.ln()
+    }
+}
+
+pub fn euclidean<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    p.iter()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| (p_i - q_i).powf(lit(2.0)))
+        .sum::<T::Float>()
         .sqrt()
 }
 
-pub fn manhattan<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn manhattan<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i - q_i).abs())
         .sum()
 }
 
-pub fn minkowski<T: Into<f64> + Copy>(p: &[T], q: &[T], d: f64) -> f64 {
+pub fn minkowski<T: Element>(p: &[T], q: &[T], d: f64) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| (p_i - q_i).abs().powf(d))
-        .sum::<f64>()
-        .powf(1.0 / d)
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| (p_i - q_i).abs().powf(lit(d)))
+        .sum::<T::Float>()
+        .powf(lit(1.0 / d))
 }
 
-pub fn chebyshev<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn chebyshev<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i - q_i).abs())
-        .fold(0.0, f64::max)
+        .fold(T::Float::zero(), Float::max)
 }
 
-pub fn sorensen<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| (p_i - q_i).abs())
-        .sum::<f64>()
-        / p.iter()
-            .map(|&p| p.into())
-            .zip(q.iter().map(|&q| q.into()))
-            .map(|(p_i, q_i)| p_i + q_i)
-            .sum::<f64>()
+pub fn sorensen<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (diff, sum) = fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero()),
+        |(diff, sum), (p_i, q_i)| (diff + (p_i - q_i).abs(), sum + p_i + q_i),
+    );
+
+    diff / sum
 }
 
-pub fn gower<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn gower<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i - q_i).abs())
-        .sum::<f64>()
-        / p.len() as f64
+        .sum::<T::Float>()
+        / lit(p.len() as f64)
 }
 
-pub fn soergel<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| (p_i - q_i).abs())
-        .sum::<f64>()
-        / p.iter()
-            .map(|&p| p.into())
-            .zip(q.iter().map(|&q| q.into()))
-            .map(|(p_i, q_i)| p_i.max(q_i))
-            .sum::<f64>()
+pub fn soergel<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (diff, max) = fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero()),
+        |(diff, max), (p_i, q_i)| (diff + (p_i - q_i).abs(), max + p_i.max(q_i)),
+    );
+
+    diff / max
 }
 
-pub fn kulczynski<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| (p_i - q_i).abs())
-        .sum::<f64>()
-        / p.iter()
-            .map(|&p| p.into())
-            .zip(q.iter().map(|&q| q.into()))
-            .map(|(p_i, q_i)| p_i.min(q_i))
-            .sum::<f64>()
+pub fn kulczynski<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (diff, min) = fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero()),
+        |(diff, min), (p_i, q_i)| (diff + (p_i - q_i).abs(), min + p_i.min(q_i)),
+    );
+
+    diff / min
 }
 
-pub fn canberra<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn canberra<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i - q_i).abs() / (p_i.abs() + q_i.abs()))
         .sum()
 }
 
-pub fn lorentzian<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn lorentzian<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i - q_i).abs().ln_1p())
         .sum()
 }
 
-pub fn intersection<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn intersection<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i - q_i).abs())
-        .sum::<f64>()
-        / 2.0
+        .sum::<T::Float>()
+        / lit(2.0)
 }
 
-pub fn wave_hedges<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn wave_hedges<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i - q_i).abs() / p_i.max(q_i))
         .sum()
 }
 
-pub fn czekanowski<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    1.0 - 2.0
-        * p.iter()
-            .map(|&p| p.into())
-            .zip(q.iter().map(|&q| q.into()))
-            .map(|(p_i, q_i)| p_i.min(q_i))
-            .sum::<f64>()
-        / p.iter()
-            .map(|&p| p.into())
-            .zip(q.iter().map(|&q| q.into()))
-            .map(|(p_i, q_i)| p_i + q_i)
-            .sum::<f64>()
-}
-
-pub fn motyka<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i.max(q_i))
-        .sum::<f64>()
-        / p.iter()
-            .map(|&p| p.into())
-            .zip(q.iter().map(|&q| q.into()))
-            .map(|(p_i, q_i)| p_i + q_i)
-            .sum::<f64>()
-}
-
-pub fn ruzicka<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i.min(q_i))
-        .sum::<f64>()
-        / p.iter()
-            .map(|&p| p.into())
-            .zip(q.iter().map(|&q| q.into()))
-            .map(|(p_i, q_i)| p_i.max(q_i))
-            .sum::<f64>()
+pub fn czekanowski<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (min, sum) = fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero()),
+        |(min, sum), (p_i, q_i)| (min + p_i.min(q_i), sum + p_i + q_i),
+    );
+
+    lit::<T::Float>(1.0) - lit::<T::Float>(2.0) * min / sum
 }
 
-pub fn tanimoto<T: Into<f64> + Copy>(_p: &[T], _q: &[T]) -> f64 {
-    unimplemented!()
+pub fn motyka<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (max, sum) = fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero()),
+        |(max, sum), (p_i, q_i)| (max + p_i.max(q_i), sum + p_i + q_i),
+    );
+
+    max / sum
 }
 
-pub fn inner_product<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i * q_i)
-        .sum()
+pub fn ruzicka<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (min, max) = fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero()),
+        |(min, max), (p_i, q_i)| (min + p_i.min(q_i), max + p_i.max(q_i)),
+    );
+
+    min / max
+}
+
+pub fn tanimoto<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (max, min) = fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero()),
+        |(max, min), (p_i, q_i)| (max + p_i.max(q_i), min + p_i.min(q_i)),
+    );
+
+    (max - min) / max
 }
 
-pub fn harmonic_mean<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    2.0 * p
+// Fingerprint variant: every non-zero coordinate is treated as a set bit
+pub fn tanimoto_bitvector<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (p_bits, q_bits, common) = p
         .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| (p_i * q_i) / (p_i + q_i))
-        .sum::<f64>()
+        .map(|&p| !p.to_float().is_zero())
+        .zip(q.iter().map(|&q| !q.to_float().is_zero()))
+        .fold((0, 0, 0), |(p_bits, q_bits, common), (p_i, q_i)| {
+            (
+                p_bits + usize::from(p_i),
+                q_bits + usize::from(q_i),
+                common + usize::from(p_i && q_i),
+            )
+        });
+
+    lit::<T::Float>((p_bits + q_bits - 2 * common) as f64) / lit((p_bits + q_bits - common) as f64)
 }
 
-pub fn jaccard<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    let pq = p
+// Number of positions at which the vectors differ
+pub fn hamming<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let count = p
         .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i * q_i)
-        .sum::<f64>();
+        .zip(q.iter())
+        .filter(|(&p_i, &q_i)| p_i.to_float() != q_i.to_float())
+        .count();
 
-    1.0 - (pq
-        / (p.iter()
-            .map(|&p| p.into())
-            .map(|p_i| p_i * p_i)
-            .sum::<f64>()
-            + q.iter()
-                .map(|&q| q.into())
-                .map(|q_i| q_i * q_i)
-                .sum::<f64>()
-            - pq))
+    lit(count as f64)
 }
 
-pub fn dice<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn inner_product<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| (p_i - q_i) * (p_i - q_i))
-        .sum::<f64>()
-        / (p.iter()
-            .map(|&p| p.into())
-            .map(|p_i| p_i * p_i)
-            .sum::<f64>()
-            + q.iter()
-                .map(|&q| q.into())
-                .map(|q_i| q_i * q_i)
-                .sum::<f64>())
-}
-
-pub fn bhattacharyya<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| p_i * q_i)
+        .sum()
+}
+
+pub fn harmonic_mean<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    lit::<T::Float>(2.0)
+        * p.iter()
+            .map(|&p| p.to_float())
+            .zip(q.iter().map(|&q| q.to_float()))
+            .map(|(p_i, q_i)| (p_i * q_i) / (p_i + q_i))
+            .sum::<T::Float>()
+}
+
+pub fn jaccard<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (pq, pp, qq) = dot_products(p, q);
+
+    lit::<T::Float>(1.0) - pq / (pp + qq - pq)
+}
+
+pub fn dice<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (diff, pp, qq) = fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero(), T::Float::zero()),
+        |(diff, pp, qq), (p_i, q_i)| {
+            (
+                diff + (p_i - q_i) * (p_i - q_i),
+                pp + p_i * p_i,
+                qq + q_i * q_i,
+            )
+        },
+    );
+
+    diff / (pp + qq)
+}
+
+pub fn bhattacharyya<T: Element>(p: &[T], q: &[T]) -> T::Float {
     -(p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i * q_i).sqrt())
-        .sum::<f64>())
+        .sum::<T::Float>())
     .ln()
 }
 
-pub fn hellinger<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    (2.0 * p
-        .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| (p_i.sqrt() - q_i.sqrt()).powi(2))
-        .sum::<f64>())
+pub fn hellinger<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    (lit::<T::Float>(2.0)
+        * p.iter()
+            .map(|&p| p.to_float())
+            .zip(q.iter().map(|&q| q.to_float()))
+            .map(|(p_i, q_i)| (p_i.sqrt() - q_i.sqrt()).powi(2))
+            .sum::<T::Float>())
     .sqrt()
 }
 
-pub fn matusita<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn matusita<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i.sqrt() - q_i.sqrt()).powi(2))
-        .sum::<f64>()
+        .sum::<T::Float>()
         .sqrt()
 }
 
-pub fn squared_chord<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn squared_chord<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i.sqrt() - q_i.sqrt()).powi(2))
-        .sum::<f64>()
+        .sum::<T::Float>()
 }
 
-pub fn squared_euclidean<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn squared_euclidean<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i - q_i).powi(2))
-        .sum::<f64>()
+        .sum::<T::Float>()
 }
 
-pub fn pearson<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn pearson<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| ((p_i - q_i).powi(2) / q_i))
-        .sum::<f64>()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| (p_i - q_i).powi(2) / q_i)
+        .sum::<T::Float>()
 }
 
-pub fn neyman<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn neyman<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| ((p_i - q_i).powi(2) / p_i))
-        .sum::<f64>()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| (p_i - q_i).powi(2) / p_i)
+        .sum::<T::Float>()
 }
 
-pub fn squared<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn squared<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| ((p_i - q_i).powi(2) / (p_i + q_i)))
-        .sum::<f64>()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| (p_i - q_i).powi(2) / (p_i + q_i))
+        .sum::<T::Float>()
 }
 
-pub fn probabilistic_symmetric<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    2.0 * squared(p, q)
+pub fn probabilistic_symmetric<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    lit::<T::Float>(2.0) * squared(p, q)
 }
 
-pub fn divergence<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    2.0 * p
-        .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| ((p_i - q_i).powi(2) / (p_i + q_i).powi(2)))
-        .sum::<f64>()
+pub fn divergence<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    lit::<T::Float>(2.0)
+        * p.iter()
+            .map(|&p| p.to_float())
+            .zip(q.iter().map(|&q| q.to_float()))
+            .map(|(p_i, q_i)| (p_i - q_i).powi(2) / (p_i + q_i).powi(2))
+            .sum::<T::Float>()
 }
 
-pub fn clark<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn clark<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| ((p_i - q_i).abs() / (p_i + q_i)).powi(2))
-        .sum::<f64>()
+        .sum::<T::Float>()
         .sqrt()
 }
 
-pub fn additive_symmetric<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn additive_symmetric<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| ((p_i - q_i).powi(2) * (p_i + q_i)) / (p_i * q_i))
-        .sum::<f64>()
+        .sum::<T::Float>()
 }
 
-pub fn kullback_leibler<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn kullback_leibler<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i * (p_i / q_i).ln())
-        .sum::<f64>()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| x_ln(p_i, p_i / q_i))
+        .sum::<T::Float>()
 }
 
-pub fn jeffreys<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn jeffreys<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| (p_i - q_i) * (p_i / q_i).ln())
-        .sum::<f64>()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| x_ln(p_i - q_i, p_i / q_i))
+        .sum::<T::Float>()
 }
 
-pub fn k_divergence<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn k_divergence<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i * ((2.0 * p_i) / (p_i + q_i)).ln())
-        .sum::<f64>()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| x_ln(p_i, (lit::<T::Float>(2.0) * p_i) / (p_i + q_i)))
+        .sum::<T::Float>()
 }
 
-pub fn topsoe<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn topsoe<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| {
-            (p_i * ((2.0 * p_i) / (p_i + q_i)).ln()) + (q_i * ((2.0 * q_i) / (p_i + q_i)).ln())
+            x_ln(p_i, (lit::<T::Float>(2.0) * p_i) / (p_i + q_i))
+                + x_ln(q_i, (lit::<T::Float>(2.0) * q_i) / (p_i + q_i))
         })
-        .sum::<f64>()
+        .sum::<T::Float>()
 }
 
-pub fn jensen_shannon<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn jensen_shannon<T: Element>(p: &[T], q: &[T]) -> T::Float {
     let s1 = p
         .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i * ((2.0 * p_i) / (p_i + q_i)).ln())
-        .sum::<f64>();
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| x_ln(p_i, (lit::<T::Float>(2.0) * p_i) / (p_i + q_i)))
+        .sum::<T::Float>();
 
     let s2 = p
         .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| q_i * ((2.0 * q_i) / (p_i + q_i)).ln())
-        .sum::<f64>();
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| x_ln(q_i, (lit::<T::Float>(2.0) * q_i) / (p_i + q_i)))
+        .sum::<T::Float>();
 
-    (s1 + s2) / 2.0
+    (s1 + s2) / lit(2.0)
 }
 
-pub fn jensen_difference<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn jensen_difference<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| {
-            ((p_i * p_i.ln() + q_i * q_i.ln()) / 2.0)
-                - ((p_i + q_i) / 2.0) * ((p_i + q_i) / 2.0).ln()
+            let m_i = (p_i + q_i) / lit(2.0);
+            (x_ln(p_i, p_i) + x_ln(q_i, q_i)) / lit(2.0) - x_ln(m_i, m_i)
         })
-        .sum::<f64>()
+        .sum::<T::Float>()
 }
 
-pub fn taneja<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn taneja<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| ((p_i + q_i) / 2.0) * ((p_i + q_i) / (2.0 * (p_i * q_i).sqrt())).ln())
-        .sum::<f64>()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| {
+            x_ln(
+                (p_i + q_i) / lit::<T::Float>(2.0),
+                (p_i + q_i) / (lit::<T::Float>(2.0) * (p_i * q_i).sqrt()),
+            )
+        })
+        .sum::<T::Float>()
 }
 
-pub fn kumar_johnson<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn kumar_johnson<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| ((p_i * p_i - q_i * q_i).powi(2)) / (2.0 * (p_i * q_i).powf(3.0 / 2.0)))
-        .sum::<f64>()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .map(|(p_i, q_i)| {
+            ((p_i * p_i - q_i * q_i).powi(2)) / (lit::<T::Float>(2.0) * (p_i * q_i).powf(lit(1.5)))
+        })
+        .sum::<T::Float>()
 }
 
-pub fn avg<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn avg<T: Element>(p: &[T], q: &[T]) -> T::Float {
     let sum = p
         .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i - q_i).abs())
-        .sum::<f64>();
+        .sum::<T::Float>();
 
     let max = p
         .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i - q_i).abs())
-        .fold(f64::NEG_INFINITY, f64::max);
+        .fold(T::Float::neg_infinity(), Float::max);
 
-    (sum + max) / 2.0
+    (sum + max) / lit(2.0)
 }
 
+// Euclidean distance with every squared difference divided by the variance of
+// its feature
+pub fn standardized_euclidean<T: Element>(p: &[T], q: &[T], variances: &[T::Float]) -> T::Float {
+    p.iter()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .zip(variances.iter())
+        .map(|((p_i, q_i), &v_i)| (p_i - q_i).powi(2) / v_i)
+        .sum::<T::Float>()
+        .sqrt()
+}
+
+// `(p - q)^T VI (p - q)`, and the sum of the magnitudes of its terms, which
+// bounds the rounding error
+fn quadratic_form<T: Element>(
+    p: &[T],
+    q: &[T],
+    inverse_covariance: &[T::Float],
+) -> (T::Float, T::Float) {
+    let diff: Vec<T::Float> = p
+        .iter()
+        .zip(q.iter())
+        .map(|(&p_i, &q_i)| p_i.to_float() - q_i.to_float())
+        .collect();
+    assert_eq!(
+        inverse_covariance.len(),
+        diff.len() * diff.len(),
+        "inverse covariance is not n * n"
+    );
+
+    let zero = T::Float::zero();
+    inverse_covariance
+        .chunks_exact(diff.len())
+        .zip(&diff)
+        .flat_map(|(row, &d_i)| row.iter().zip(&diff).map(move |(&v, &d_j)| d_i * v * d_j))
+        .fold((zero, zero), |(form, magnitude), term| {
+            (form + term, magnitude + term.abs())
+        })
+}
+
+// `sqrt((p - q)^T VI (p - q))` for the row-major `n * n` inverse covariance `VI`.
+// A negative quadratic form, from rounding or from a `VI` that is not positive
+// definite, is clamped to a distance of zero; `try_mahalanobis` reports the
+// latter as an error.
+pub fn mahalanobis<T: Element>(p: &[T], q: &[T], inverse_covariance: &[T::Float]) -> T::Float {
+    let (form, _) = quadratic_form(p, q, inverse_covariance);
+    form.max(T::Float::zero()).sqrt()
+}
+
+// Same distance from the lower-triangular Cholesky factor `L` of the covariance
+// (`V = L L^T`), as the norm of `L^-1 (p - q)`. Avoids forming the inverse.
+pub fn mahalanobis_cholesky<T: Element>(p: &[T], q: &[T], factor: &[T::Float]) -> T::Float {
+    let mut y: Vec<T::Float> = p
+        .iter()
+        .zip(q.iter())
+        .map(|(&p_i, &q_i)| p_i.to_float() - q_i.to_float())
+        .collect();
+    let n = y.len();
+    assert_eq!(factor.len(), n * n, "cholesky factor is not n * n");
+
+    for i in 0..n {
+        let sum = (0..i).fold(T::Float::zero(), |sum, k| sum + factor[i * n + k] * y[k]);
+        y[i] = (y[i] - sum) / factor[i * n + i];
+    }
+    y.iter().map(|&y_i| y_i * y_i).sum::<T::Float>().sqrt()
+}
+
+pub fn try_standardized_euclidean<T: Element>(
+    p: &[T],
+    q: &[T],
+    variances: &[T::Float],
+) -> crate::Result<T::Float> {
+    check_pair(p, q)?;
+    if variances.len() != p.len() {
+        return Err(crate::Error::VariancesLengthMismatch {
+            len: p.len(),
+            variances: variances.len(),
+        });
+    }
+    check_variances(variances)?;
+    check_result(standardized_euclidean(p, q, variances))
+}
+
+// A zero variance, e.g. of a constant feature, would divide by zero
+fn check_variances<F: Float>(variances: &[F]) -> crate::Result<()> {
+    if let Some(index) = variances.iter().position(|v| !v.is_finite()) {
+        return Err(crate::Error::NonFinite { index });
+    }
+    match variances.iter().position(|&v| v <= F::zero()) {
+        Some(index) => Err(crate::Error::NonPositiveVariance { index }),
+        None => Ok(()),
+    }
+}
+
+pub fn try_mahalanobis<T: Element>(
+    p: &[T],
+    q: &[T],
+    inverse_covariance: &[T::Float],
+) -> crate::Result<T::Float> {
+    check_pair(p, q)?;
+    if inverse_covariance.len() != p.len() * p.len() {
+        return Err(crate::Error::MatrixLengthMismatch {
+            len: p.len() * p.len(),
+            matrix: inverse_covariance.len(),
+        });
+    }
+    // Only a form below what rounding explains comes from the matrix
+    let (form, magnitude) = quadratic_form(p, q, inverse_covariance);
+    let tolerance = lit::<T::Float>((p.len() * p.len()) as f64) * T::Float::epsilon() * magnitude;
+    if form < -tolerance {
+        return Err(crate::Error::NotPositiveDefinite);
+    }
+    check_result(form.max(T::Float::zero()).sqrt())
+}
+
+pub fn try_minkowski<T: Element>(p: &[T], q: &[T], d: f64) -> crate::Result<T::Float> {
+    check_pair(p, q)?;
+    // Only a positive order keeps the powers away from a zero base
+    if d > 0.0 {
+        check_overflow(minkowski(p, q, d))
+    } else {
+        check_result(minkowski(p, q, d))
+    }
+}
+
+fallible! {
+    check_pair, check_overflow;
+    try_euclidean => euclidean,
+    try_manhattan => manhattan,
+    try_chebyshev => chebyshev,
+    try_gower => gower,
+    try_hamming => hamming,
+    try_inner_product => inner_product,
+    try_squared_euclidean => squared_euclidean,
+    try_avg => avg,
+}
+
+fallible! {
+    check_pair;
+    try_sorensen => sorensen,
+    try_soergel => soergel,
+    try_kulczynski => kulczynski,
+    try_canberra => canberra,
+    try_lorentzian => lorentzian,
+    try_intersection => intersection,
+    try_wave_hedges => wave_hedges,
+    try_czekanowski => czekanowski,
+    try_motyka => motyka,
+    try_ruzicka => ruzicka,
+    try_tanimoto => tanimoto,
+    try_tanimoto_bitvector => tanimoto_bitvector,
+    try_harmonic_mean => harmonic_mean,
+    try_jaccard => jaccard,
+    try_dice => dice,
+    try_pearson => pearson,
+    try_neyman => neyman,
+    try_squared => squared,
+    try_probabilistic_symmetric => probabilistic_symmetric,
+    try_divergence => divergence,
+    try_clark => clark,
+    try_additive_symmetric => additive_symmetric,
+}
+
+fallible! {
+    check_distributions;
+    try_bhattacharyya => bhattacharyya,
+    try_hellinger => hellinger,
+    try_matusita => matusita,
+    try_squared_chord => squared_chord,
+    try_kullback_leibler => kullback_leibler,
+    try_jeffreys => jeffreys,
+    try_k_divergence => k_divergence,
+    try_topsoe => topsoe,
+    try_jensen_shannon => jensen_shannon,
+    try_jensen_difference => jensen_difference,
+    try_taneja => taneja,
+    try_kumar_johnson => kumar_johnson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minkowski {
+    pub d: f64,
+}
+
+impl crate::Distance for Minkowski {
+    fn distance(&self, p: &[f64], q: &[f64]) -> f64 {
+        minkowski(p, q, self.d)
+    }
+
+    fn name(&self) -> &'static str {
+        "minkowski"
+    }
+}
+
+impl crate::Metric for Minkowski {
+    fn is_metric(&self) -> bool {
+        self.d >= 1.0
+    }
+}
+
+// Only built from finite, positive variances
+#[derive(Debug, Clone, PartialEq)]
+pub struct StandardizedEuclidean {
+    variances: Vec<f64>,
+}
+
+impl StandardizedEuclidean {
+    pub fn new(variances: Vec<f64>) -> crate::Result<Self> {
+        check_variances(&variances)?;
+        Ok(StandardizedEuclidean { variances })
+    }
+
+    // Sample variances of the features of a training set. A constant feature
+    // is an error rather than an infinite scale.
+    pub fn fit(data: &[f64], dim: usize) -> crate::Result<Self> {
+        Self::new(crate::covariance::variances(data, dim)?)
+    }
+
+    // Squared scaled MAD of the features instead, robust to outliers. A
+    // feature with a MAD of zero is an error as well.
+    pub fn fit_robust(data: &[f64], dim: usize) -> crate::Result<Self> {
+        Self::new(crate::covariance::mad_variances(data, dim)?)
+    }
+
+    pub fn variances(&self) -> &[f64] {
+        &self.variances
+    }
+}
+
+impl crate::Distance for StandardizedEuclidean {
+    fn distance(&self, p: &[f64], q: &[f64]) -> f64 {
+        standardized_euclidean(p, q, &self.variances)
+    }
+
+    fn name(&self) -> &'static str {
+        "standardized_euclidean"
+    }
+}
+
+impl crate::Metric for StandardizedEuclidean {}
+
+// Only built from a covariance that inverts, so that the matrix is positive
+// definite and the distance a metric
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mahalanobis {
+    inverse_covariance: Vec<f64>,
+}
+
+impl Mahalanobis {
+    // Inverts a covariance estimate, e.g. from `covariance::ledoit_wolf`
+    pub fn from_covariance(covariance: &[f64], dim: usize) -> crate::Result<Self> {
+        Ok(Mahalanobis {
+            inverse_covariance: crate::covariance::inverse(covariance, dim)?,
+        })
+    }
+
+    pub fn inverse_covariance(&self) -> &[f64] {
+        &self.inverse_covariance
+    }
+}
+
+impl crate::Distance for Mahalanobis {
+    fn distance(&self, p: &[f64], q: &[f64]) -> f64 {
+        mahalanobis(p, q, &self.inverse_covariance)
+    }
+
+    fn name(&self) -> &'static str {
+        "mahalanobis"
+    }
+}
+
+impl crate::Metric for Mahalanobis {}
+
+// `minkowski`, `standardized_euclidean` and `mahalanobis` take parameters and
+// are therefore not resolvable by name
+measures! {
+    Distance, distance;
+    Euclidean => euclidean,
+    Manhattan => manhattan,
+    Chebyshev => chebyshev,
+    Sorensen => sorensen,
+    Gower => gower,
+    Soergel => soergel,
+    Kulczynski => kulczynski,
+    Canberra => canberra,
+    Lorentzian => lorentzian,
+    Intersection => intersection,
+    WaveHedges => wave_hedges,
+    Czekanowski => czekanowski,
+    Motyka => motyka,
+    Ruzicka => ruzicka,
+    Tanimoto => tanimoto,
+    TanimotoBitvector => tanimoto_bitvector,
+    Hamming => hamming,
+    InnerProduct => inner_product,
+    HarmonicMean => harmonic_mean,
+    Jaccard => jaccard,
+    Dice => dice,
+    Bhattacharyya => bhattacharyya,
+    Hellinger => hellinger,
+    Matusita => matusita,
+    SquaredChord => squared_chord,
+    SquaredEuclidean => squared_euclidean,
+    Pearson => pearson,
+    Neyman => neyman,
+    Squared => squared,
+    ProbabilisticSymmetric => probabilistic_symmetric,
+    Divergence => divergence,
+    Clark => clark,
+    AdditiveSymmetric => additive_symmetric,
+    KullbackLeibler => kullback_leibler,
+    Jeffreys => jeffreys,
+    KDivergence => k_divergence,
+    Topsoe => topsoe,
+    JensenShannon => jensen_shannon,
+    JensenDifference => jensen_difference,
+    Taneja => taneja,
+    KumarJohnson => kumar_johnson,
+    Avg => avg,
+}
+
+macro_rules! metrics {
+    ($($name:ident),* $(,)?) => {
+        $(impl crate::Metric for $name {})*
+    };
+}
+
+// The ratio and square-root measures are metrics on non-negative inputs
+metrics!(
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+    Gower,
+    Soergel,
+    Canberra,
+    Lorentzian,
+    Intersection,
+    Tanimoto,
+    TanimotoBitvector,
+    Hamming,
+    Hellinger,
+    Matusita,
+    Clark,
+    Avg,
+);
+
 #[cfg(test)]
 mod tests {
-    use crate::distance;
+    use crate::{covariance, distance, Distance, Error};
 
     const P: [f64; 3] = [0.000, 1.700, 2.350];
     const Q: [f64; 3] = [0.300, 1.700, 1.001];
@@ -418,6 +818,21 @@ mod tests {
         assert_relative_eq!(result, 1.381955499, epsilon = 1e-9);
     }
 
+    #[test]
+    fn euclidean_f32() {
+        let p = P.map(|v| v as f32);
+        let q = Q.map(|v| v as f32);
+        let result: f32 = distance::euclidean(&p, &q);
+        assert_relative_eq!(result, 1.381_955_5, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn hamming_bool() {
+        let p = [true, false, true, true];
+        let q = [true, true, false, true];
+        assert_eq!(distance::hamming(&p, &q), 2.0);
+    }
+
     #[test]
     fn manhattan() {
         let result = distance::manhattan(&P, &Q);
@@ -502,6 +917,27 @@ mod tests {
         assert_relative_eq!(result, 0.620919540, epsilon = 1e-9);
     }
 
+    #[test]
+    fn tanimoto() {
+        let result = distance::tanimoto(&P, &Q);
+        assert_relative_eq!(result, 0.3790804598, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn tanimoto_bitvector() {
+        let p = [1, 0, 1, 1, 0, 1];
+        let q = [1, 1, 0, 1, 0, 0];
+        let result = distance::tanimoto_bitvector(&p, &q);
+        assert_relative_eq!(result, 0.6, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn hamming() {
+        let result = distance::hamming(&P, &Q);
+        assert_relative_eq!(result, 2.0, epsilon = 1e-9);
+        assert_relative_eq!(distance::hamming(&[1, 0, 1, 1], &[1, 1, 0, 1]), 2.0);
+    }
+
     #[test]
     fn inner_product() {
         let result = distance::inner_product(&P, &Q);
@@ -586,4 +1022,225 @@ mod tests {
         let result = distance::divergence(&P, &Q);
         assert_relative_eq!(result, 2.32411941, epsilon = 1e-9);
     }
+
+    #[test]
+    fn try_euclidean() {
+        let result = distance::try_euclidean(&P, &Q).unwrap();
+        assert_relative_eq!(result, 1.381955499, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn try_length_mismatch() {
+        let result = distance::try_manhattan(&P, &Q[..2]);
+        assert_eq!(result, Err(Error::LengthMismatch { p: 3, q: 2 }));
+    }
+
+    #[test]
+    fn try_empty_input() {
+        let empty: [f64; 0] = [];
+        let result = distance::try_minkowski(&empty, &empty, 3.0);
+        assert_eq!(result, Err(Error::EmptyInput));
+    }
+
+    #[test]
+    fn try_non_finite() {
+        let result = distance::try_euclidean(&P, &[0.3, f64::NAN, 1.001]);
+        assert_eq!(result, Err(Error::NonFinite { index: 1 }));
+    }
+
+    #[test]
+    fn try_negative_probability() {
+        let result = distance::try_hellinger(&P, &[0.3, -1.7, 1.001]);
+        assert_eq!(result, Err(Error::NegativeProbability { index: 1 }));
+    }
+
+    #[test]
+    fn try_division_by_zero() {
+        let result = distance::try_pearson(&Q, &P);
+        assert_eq!(result, Err(Error::DivisionByZero));
+    }
+
+    #[test]
+    fn try_overflow() {
+        let result = distance::try_euclidean(&[1e200, 0.0], &[-1e200, 0.0]);
+        assert_eq!(result, Err(Error::Overflow));
+        let result = distance::try_minkowski(&[f64::MAX], &[-f64::MAX], 2.0);
+        assert_eq!(result, Err(Error::Overflow));
+    }
+
+    #[test]
+    fn empty_bins() {
+        // 0 * ln(0) counts as 0, so zeros in `p` are fine
+        let p = [0.5, 0.5, 0.0];
+        let q = [0.25, 0.25, 0.5];
+        let result = distance::try_kullback_leibler(&p, &q).unwrap();
+        assert_relative_eq!(result, 2.0_f64.ln(), epsilon = 1e-12);
+        let result = distance::try_jensen_shannon(&p, &q).unwrap();
+        assert_relative_eq!(result, 0.215_761_554, epsilon = 1e-9);
+        assert_relative_eq!(distance::jensen_difference(&p, &q), result, epsilon = 1e-12);
+        assert!(distance::try_topsoe(&p, &q).is_ok());
+        assert!(distance::try_k_divergence(&p, &q).is_ok());
+
+        // A bin that `q` leaves empty makes the divergence infinite
+        let result = distance::try_kullback_leibler(&q, &p);
+        assert_eq!(result, Err(Error::DivisionByZero));
+        let result = distance::try_jeffreys(&[0.0, 1.0], &[0.0, 1.0]);
+        assert_eq!(result, Ok(0.0));
+    }
+
+    #[test]
+    fn readme_names_resolve() {
+        let readme = include_str!("../README.md");
+        let table = readme.split("## Distances Implemented").nth(1).unwrap();
+        let rows = table.lines().skip_while(|l| !l.starts_with('|'));
+        for row in rows.take_while(|l| l.starts_with('|')).skip(2) {
+            let name = row
+                .trim_start_matches('|')
+                .split_whitespace()
+                .next()
+                .unwrap();
+            // Parametrised, see the footnote
+            if name.ends_with('¹') {
+                assert!(distance::by_name(name.trim_end_matches('¹')).is_none());
+                continue;
+            }
+            assert!(distance::by_name(name).is_some(), "{name} does not resolve");
+        }
+    }
+
+    #[test]
+    fn by_name() {
+        let measure = distance::by_name("kullbackLeibler").unwrap();
+        assert_eq!(measure.name(), "kullback_leibler");
+        assert_eq!(measure.distance(&Q, &P), distance::kullback_leibler(&Q, &P));
+
+        let measure = distance::by_name("wave_hedges").unwrap();
+        assert_relative_eq!(measure.distance(&P, &Q), 1.574042553, epsilon = 1e-9);
+
+        assert!(distance::by_name("kdivergence").is_some());
+        assert!(distance::by_name("unknown").is_none());
+    }
+
+    #[test]
+    fn standardized_euclidean() {
+        let result = distance::standardized_euclidean(&P, &Q, &[0.5, 1.0, 2.0]);
+        assert_relative_eq!(result, 1.043982998, epsilon = 1e-9);
+
+        let result = distance::standardized_euclidean(&P, &Q, &[1.0, 1.0, 1.0]);
+        assert_relative_eq!(result, distance::euclidean(&P, &Q), epsilon = 1e-12);
+
+        let result = distance::try_standardized_euclidean(&P, &Q, &[1.0, 0.0, 1.0]);
+        assert_eq!(result, Err(Error::NonPositiveVariance { index: 1 }));
+        let result = distance::try_standardized_euclidean(&P, &Q, &[1.0, 1.0, -2.0]);
+        assert_eq!(result, Err(Error::NonPositiveVariance { index: 2 }));
+        let result = distance::try_standardized_euclidean(&P, &Q, &[f64::NAN, 1.0, 1.0]);
+        assert_eq!(result, Err(Error::NonFinite { index: 0 }));
+        let result = distance::try_standardized_euclidean(&P, &Q, &[1.0]);
+        assert_eq!(
+            result,
+            Err(Error::VariancesLengthMismatch {
+                len: 3,
+                variances: 1
+            })
+        );
+        let result = distance::try_standardized_euclidean(&P, &Q[..1], &[1.0]);
+        assert_eq!(result, Err(Error::LengthMismatch { p: 3, q: 1 }));
+    }
+
+    #[test]
+    fn standardized_euclidean_fit() {
+        let data = [0.0, 10.0, 1.0, 30.0, 2.0, 20.0, 3.0, 40.0];
+        let measure = distance::StandardizedEuclidean::fit(&data, 2).unwrap();
+        assert_relative_eq!(measure.variances()[0], 5.0 / 3.0, epsilon = 1e-12);
+        assert_relative_eq!(measure.variances()[1], 500.0 / 3.0, epsilon = 1e-12);
+        // One standard deviation along each feature
+        let (sd_0, sd_1) = (measure.variances()[0].sqrt(), measure.variances()[1].sqrt());
+        assert_relative_eq!(
+            measure.distance(&[0.0, 0.0], &[sd_0, sd_1]),
+            2f64.sqrt(),
+            epsilon = 1e-12
+        );
+
+        let robust = distance::StandardizedEuclidean::fit_robust(&data, 2).unwrap();
+        assert_relative_eq!(robust.variances()[0], 1.4826 * 1.4826, epsilon = 1e-12);
+
+        // The second feature is constant, and its MAD is zero in both
+        let constant = [0.0, 5.0, 1.0, 5.0, 2.0, 5.0, 3.0, 5.0];
+        assert_eq!(
+            distance::StandardizedEuclidean::fit(&constant, 2),
+            Err(Error::NonPositiveVariance { index: 1 })
+        );
+        assert_eq!(
+            distance::StandardizedEuclidean::fit_robust(&constant, 2),
+            Err(Error::NonPositiveVariance { index: 1 })
+        );
+        let outlier = [0.0, 5.0, 1.0, 5.0, 2.0, 5.0, 3.0, 9.0];
+        assert!(distance::StandardizedEuclidean::fit(&outlier, 2).is_ok());
+        assert_eq!(
+            distance::StandardizedEuclidean::fit_robust(&outlier, 2),
+            Err(Error::NonPositiveVariance { index: 1 })
+        );
+    }
+
+    #[test]
+    fn mahalanobis() {
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let result = distance::mahalanobis(&P, &Q, &identity);
+        assert_relative_eq!(result, distance::euclidean(&P, &Q), epsilon = 1e-12);
+
+        let inverse = [4.0, 1.0, 0.0, 1.0, 2.0, 0.5, 0.0, 0.5, 1.0];
+        let result = distance::mahalanobis(&P, &Q, &inverse);
+        assert_relative_eq!(result, 1.476414915, epsilon = 1e-9);
+
+        let covariance = covariance::inverse(&inverse, 3).unwrap();
+        let factor = covariance::cholesky(&covariance, 3).unwrap();
+        let result = distance::mahalanobis_cholesky(&P, &Q, &factor);
+        assert_relative_eq!(result, 1.476414915, epsilon = 1e-9);
+
+        let measure = distance::Mahalanobis::from_covariance(&covariance, 3).unwrap();
+        assert_relative_eq!(measure.distance(&P, &Q), 1.476414915, epsilon = 1e-9);
+        for (value, expected) in measure.inverse_covariance().iter().zip(inverse) {
+            assert_relative_eq!(*value, expected, epsilon = 1e-12);
+        }
+
+        let singular = [1.0, 1.0, 1.0, 1.0];
+        assert_eq!(
+            distance::Mahalanobis::from_covariance(&singular, 2),
+            Err(Error::NotPositiveDefinite)
+        );
+    }
+
+    #[test]
+    fn try_mahalanobis() {
+        let result = distance::try_mahalanobis(&P, &Q, &[1.0; 4]);
+        assert_eq!(
+            result,
+            Err(Error::MatrixLengthMismatch { len: 9, matrix: 4 })
+        );
+        let result = distance::try_mahalanobis(&P, &Q[..2], &[1.0; 9]);
+        assert_eq!(result, Err(Error::LengthMismatch { p: 3, q: 2 }));
+
+        // Indefinite: the unchecked distance is clamped to zero
+        let indefinite = [-1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, -1.0];
+        assert_eq!(distance::mahalanobis(&P, &Q, &indefinite), 0.0);
+        let result = distance::try_mahalanobis(&P, &Q, &indefinite);
+        assert_eq!(result, Err(Error::NotPositiveDefinite));
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        assert_eq!(distance::try_mahalanobis(&P, &P, &identity), Ok(0.0));
+    }
+
+    #[test]
+    fn mahalanobis_f32() {
+        let p = P.map(|v| v as f32);
+        let q = Q.map(|v| v as f32);
+        let inverse = [4.0f32, 1.0, 0.0, 1.0, 2.0, 0.5, 0.0, 0.5, 1.0];
+        let result = distance::mahalanobis(&p, &q, &inverse);
+        assert_relative_eq!(result, 1.476_415, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn minkowski_struct() {
+        let measure = distance::Minkowski { d: 2.0 };
+        assert_relative_eq!(measure.distance(&P, &Q), 1.381955499, epsilon = 1e-9);
+    }
 }
@@ -0,0 +1,36 @@
+use num_traits::Float;
+
+// Input element of the measures. Floats are computed in their own precision,
+// integers and `bool` are widened to f64 as before. Other `Into<f64>` types
+// can implement it with `type Float = f64`.
+pub trait Element: Copy {
+    type Float: Float + std::iter::Sum + std::fmt::Debug;
+
+    fn to_float(self) -> Self::Float;
+}
+
+macro_rules! element {
+    ($float:ty; $($t:ty),*) => {
+        $(
+            impl Element for $t {
+                type Float = $float;
+
+                fn to_float(self) -> $float {
+                    self.into()
+                }
+            }
+        )*
+    };
+}
+
+element!(f32; f32);
+element!(f64; f64, i8, i16, i32, u8, u16, u32, bool);
+
+// Half-precision storage is widened to f32 for the computation
+#[cfg(feature = "half")]
+element!(f32; half::f16, half::bf16);
+
+// Numeric constant in the precision of the measure
+pub(crate) fn lit<F: Float>(value: f64) -> F {
+    F::from(value).unwrap()
+}
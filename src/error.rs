@@ -0,0 +1,145 @@
+use std::fmt;
+
+use num_traits::{Float, Zero};
+
+use crate::element::Element;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Error {
+    LengthMismatch { p: usize, q: usize },
+    EmptyInput,
+    NonFinite { index: usize },
+    NegativeProbability { index: usize },
+    NegativeWeight { index: usize },
+    WeightsLengthMismatch { len: usize, weights: usize },
+    MatrixLengthMismatch { len: usize, matrix: usize },
+    VariancesLengthMismatch { len: usize, variances: usize },
+    DivisionByZero,
+    NotPositiveDefinite,
+    NonPositiveVariance { index: usize },
+    TypeMismatch { index: usize },
+    Overflow,
+    NothingObserved,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::LengthMismatch { p, q } => {
+                write!(f, "length mismatch: p has {p} elements, q has {q}")
+            }
+            Error::EmptyInput => write!(f, "input vectors are empty"),
+            Error::NonFinite { index } => write!(f, "non-finite value at index {index}"),
+            Error::NegativeProbability { index } => {
+                write!(f, "negative probability at index {index}")
+            }
+            Error::NegativeWeight { index } => write!(f, "negative weight at index {index}"),
+            Error::WeightsLengthMismatch { len, weights } => {
+                write!(
+                    f,
+                    "length mismatch: vectors have {len} elements, weights have {weights}"
+                )
+            }
+            Error::MatrixLengthMismatch { len, matrix } => {
+                write!(
+                    f,
+                    "length mismatch: matrix has {matrix} elements instead of {len}"
+                )
+            }
+            Error::VariancesLengthMismatch { len, variances } => {
+                write!(
+                    f,
+                    "length mismatch: vectors have {len} elements, variances have {variances}"
+                )
+            }
+            Error::DivisionByZero => write!(f, "division by zero"),
+            Error::NotPositiveDefinite => write!(f, "matrix is not positive definite"),
+            Error::NonPositiveVariance { index } => {
+                write!(f, "variance at index {index} is not positive")
+            }
+            Error::TypeMismatch { index } => {
+                write!(f, "value at index {index} does not match its column")
+            }
+            Error::Overflow => write!(f, "result overflows the float type"),
+            Error::NothingObserved => write!(f, "no coordinate is observed in both vectors"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub(crate) fn check_pair<T: Element>(p: &[T], q: &[T]) -> Result<()> {
+    if p.len() != q.len() {
+        return Err(Error::LengthMismatch {
+            p: p.len(),
+            q: q.len(),
+        });
+    }
+    if p.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    match p
+        .iter()
+        .zip(q.iter())
+        .position(|(&p_i, &q_i)| !p_i.to_float().is_finite() || !q_i.to_float().is_finite())
+    {
+        Some(index) => Err(Error::NonFinite { index }),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn check_distributions<T: Element>(p: &[T], q: &[T]) -> Result<()> {
+    check_pair(p, q)?;
+    match p
+        .iter()
+        .zip(q.iter())
+        .position(|(&p_i, &q_i)| p_i.to_float() < Zero::zero() || q_i.to_float() < Zero::zero())
+    {
+        Some(index) => Err(Error::NegativeProbability { index }),
+        None => Ok(()),
+    }
+}
+
+// The inputs are finite once validated, and the logarithmic measures take
+// `0 * ln(0)` as 0, so a non-finite result comes from a zero denominator.
+pub(crate) fn check_result<F: Float>(result: F) -> Result<F> {
+    if result.is_finite() {
+        Ok(result)
+    } else {
+        Err(Error::DivisionByZero)
+    }
+}
+
+// For the measures without any division, where only an overflow can make the
+// result non-finite
+pub(crate) fn check_overflow<F: Float>(result: F) -> Result<F> {
+    if result.is_finite() {
+        Ok(result)
+    } else {
+        Err(Error::Overflow)
+    }
+}
+
+// Generates the `try_*` counterparts of the infallible measures. The result is
+// checked with `check_result` unless another checker follows the input one.
+macro_rules! fallible {
+    ($check:path; $($name:ident => $measure:ident),* $(,)?) => {
+        $crate::error::fallible! {
+            $check, $crate::error::check_result;
+            $($name => $measure),*
+        }
+    };
+    ($check:path, $result:path; $($name:ident => $measure:ident),* $(,)?) => {
+        $(
+            #[doc = concat!("Checked version of [`", stringify!($measure), "`].")]
+            pub fn $name<T: $crate::Element>(p: &[T], q: &[T]) -> $crate::Result<T::Float> {
+                $check(p, q)?;
+                $result($measure(p, q))
+            }
+        )*
+    };
+}
+
+pub(crate) use fallible;
@@ -0,0 +1,241 @@
+// Gower's coefficient for records mixing numeric, categorical and boolean
+// features, unlike `distance::gower` which only averages numeric differences.
+// Every column contributes a dissimilarity in [0, 1]:
+//
+// - numeric: the absolute difference divided by the fitted range
+// - categorical and symmetric binary: 0 when equal, 1 otherwise
+// - asymmetric binary: as symmetric, but two `false` values are not compared
+//
+// Missing values are not compared either, and the result is the mean over the
+// columns that were.
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Numeric(f64),
+    Categorical(String),
+    Binary(bool),
+    Missing,
+}
+
+impl Value {
+    fn is_missing(&self) -> bool {
+        match self {
+            Value::Missing => true,
+            Value::Numeric(v) => v.is_nan(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Numeric,
+    Categorical,
+    SymmetricBinary,
+    AsymmetricBinary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Numeric { range: f64 },
+    Categorical,
+    SymmetricBinary,
+    AsymmetricBinary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    pub columns: Vec<Column>,
+}
+
+impl Schema {
+    pub fn new(columns: Vec<Column>) -> Self {
+        Schema { columns }
+    }
+
+    // Schema of the given kinds, with the range of every numeric column taken
+    // from `records`. Columns without any observed value get a zero range.
+    pub fn fit<R: AsRef<[Value]>>(kinds: &[Kind], records: &[R]) -> Result<Self> {
+        let mut ranges = vec![(f64::INFINITY, f64::NEG_INFINITY); kinds.len()];
+        for record in records {
+            let record = check_len(kinds.len(), record.as_ref())?;
+            for (index, (value, kind)) in record.iter().zip(kinds).enumerate() {
+                match (kind, value) {
+                    (_, value) if value.is_missing() => {}
+                    (Kind::Numeric, Value::Numeric(v)) => {
+                        let (min, max) = &mut ranges[index];
+                        *min = min.min(*v);
+                        *max = max.max(*v);
+                    }
+                    (Kind::Categorical, Value::Categorical(_))
+                    | (Kind::SymmetricBinary | Kind::AsymmetricBinary, Value::Binary(_)) => {}
+                    _ => return Err(Error::TypeMismatch { index }),
+                }
+            }
+        }
+
+        let columns = kinds
+            .iter()
+            .zip(ranges)
+            .map(|(kind, (min, max))| match kind {
+                Kind::Numeric => Column::Numeric {
+                    range: if max > min { max - min } else { 0.0 },
+                },
+                Kind::Categorical => Column::Categorical,
+                Kind::SymmetricBinary => Column::SymmetricBinary,
+                Kind::AsymmetricBinary => Column::AsymmetricBinary,
+            })
+            .collect();
+        Ok(Schema { columns })
+    }
+
+    // Gower distance between two records, in [0, 1]
+    pub fn distance(&self, a: &[Value], b: &[Value]) -> Result<f64> {
+        check_len(self.columns.len(), a)?;
+        check_len(self.columns.len(), b)?;
+
+        let (mut sum, mut compared) = (0.0, 0usize);
+        for (index, ((column, a), b)) in self.columns.iter().zip(a).zip(b).enumerate() {
+            if a.is_missing() || b.is_missing() {
+                continue;
+            }
+            let term = match (column, a, b) {
+                // A constant column cannot tell records apart
+                (Column::Numeric { range }, Value::Numeric(a), Value::Numeric(b)) => {
+                    if *range > 0.0 {
+                        ((a - b).abs() / range).min(1.0)
+                    } else {
+                        0.0
+                    }
+                }
+                (Column::Categorical, Value::Categorical(a), Value::Categorical(b)) => {
+                    f64::from(u8::from(a != b))
+                }
+                (Column::SymmetricBinary, Value::Binary(a), Value::Binary(b)) => {
+                    f64::from(u8::from(a != b))
+                }
+                (Column::AsymmetricBinary, Value::Binary(false), Value::Binary(false)) => continue,
+                (Column::AsymmetricBinary, Value::Binary(a), Value::Binary(b)) => {
+                    f64::from(u8::from(a != b))
+                }
+                _ => return Err(Error::TypeMismatch { index }),
+            };
+            sum += term;
+            compared += 1;
+        }
+
+        if compared == 0 {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(sum / compared as f64)
+    }
+}
+
+fn check_len(columns: usize, record: &[Value]) -> Result<&[Value]> {
+    if record.len() != columns {
+        return Err(Error::LengthMismatch {
+            p: columns,
+            q: record.len(),
+        });
+    }
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::distance;
+    use crate::gower::{Column, Kind, Schema, Value};
+    use crate::Error;
+
+    fn record(age: Option<f64>, colour: &str, smoker: bool, disease: bool) -> Vec<Value> {
+        vec![
+            age.map_or(Value::Missing, Value::Numeric),
+            Value::Categorical(colour.to_string()),
+            Value::Binary(smoker),
+            Value::Binary(disease),
+        ]
+    }
+
+    const KINDS: [Kind; 4] = [
+        Kind::Numeric,
+        Kind::Categorical,
+        Kind::SymmetricBinary,
+        Kind::AsymmetricBinary,
+    ];
+
+    #[test]
+    fn mixed_records() {
+        let records = [
+            record(Some(30.0), "red", true, false),
+            record(Some(50.0), "blue", true, false),
+            record(Some(40.0), "red", false, true),
+            record(None, "green", false, true),
+        ];
+        let schema = Schema::fit(&KINDS, &records).unwrap();
+        assert_eq!(schema.columns[0], Column::Numeric { range: 20.0 });
+
+        // Age 1, colour 1, smoker 0, the two `false` diseases are skipped
+        let result = schema.distance(&records[0], &records[1]).unwrap();
+        assert_relative_eq!(result, 2.0 / 3.0, epsilon = 1e-12);
+        let result = schema.distance(&records[0], &records[2]).unwrap();
+        assert_relative_eq!(result, 0.625, epsilon = 1e-12);
+        // The missing age is skipped
+        let result = schema.distance(&records[2], &records[3]).unwrap();
+        assert_relative_eq!(result, 1.0 / 3.0, epsilon = 1e-12);
+        assert_eq!(schema.distance(&records[0], &records[0]), Ok(0.0));
+    }
+
+    #[test]
+    fn numeric_only() {
+        let p = [0.000, 1.700, 2.350];
+        let q = [0.300, 1.700, 1.001];
+        let schema = Schema::new(vec![Column::Numeric { range: 10.0 }; 3]);
+        let result = schema
+            .distance(&p.map(Value::Numeric), &q.map(Value::Numeric))
+            .unwrap();
+        assert_relative_eq!(result, distance::gower(&p, &q) / 10.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn fitted_range_is_clamped() {
+        let schema = Schema::fit(
+            &[Kind::Numeric],
+            &[[Value::Numeric(0.0)], [Value::Numeric(2.0)]],
+        )
+        .unwrap();
+        let result = schema.distance(&[Value::Numeric(0.0)], &[Value::Numeric(5.0)]);
+        assert_eq!(result, Ok(1.0));
+    }
+
+    #[test]
+    fn errors() {
+        let schema = Schema::new(vec![
+            Column::Numeric { range: 1.0 },
+            Column::AsymmetricBinary,
+        ]);
+        assert_eq!(
+            schema.distance(&[Value::Numeric(1.0)], &[Value::Numeric(1.0)]),
+            Err(Error::LengthMismatch { p: 2, q: 1 })
+        );
+        assert_eq!(
+            schema.distance(
+                &[Value::Binary(true), Value::Binary(true)],
+                &[Value::Numeric(1.0), Value::Binary(true)]
+            ),
+            Err(Error::TypeMismatch { index: 0 })
+        );
+        assert_eq!(
+            schema.distance(
+                &[Value::Missing, Value::Binary(false)],
+                &[Value::Numeric(f64::NAN), Value::Binary(false)]
+            ),
+            Err(Error::DivisionByZero)
+        );
+        assert_eq!(
+            Schema::fit(&[Kind::Categorical], &[[Value::Binary(true)]]),
+            Err(Error::TypeMismatch { index: 0 })
+        );
+    }
+}
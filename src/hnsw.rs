@@ -0,0 +1,410 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+use num_traits::Float;
+
+use crate::knn::{Candidate, Neighbour};
+use crate::rng::Rng;
+use crate::simd::{self, SimdFloat};
+
+// The measures the graph can be built over. Cosine and inner product are
+// similarities (larger is closer), euclidean is a distance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Space {
+    Cosine,
+    InnerProduct,
+    Euclidean,
+}
+
+impl Space {
+    // Smaller keys are closer. The squared euclidean distance ranks like the
+    // euclidean one without the square root.
+    fn key<F: SimdFloat + Float>(&self, p: &[F], q: &[F]) -> F {
+        match self {
+            Space::Cosine => -simd::cosine(p, q),
+            Space::InnerProduct => -simd::inner_product(p, q),
+            Space::Euclidean => simd::squared_euclidean(p, q),
+        }
+    }
+
+    // The value of the measure itself for a key
+    fn score<F: Float>(&self, key: F) -> F {
+        match self {
+            Space::Cosine | Space::InnerProduct => -key,
+            Space::Euclidean => key.sqrt(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Params {
+    // Links per node on the upper layers, twice as many on the bottom one
+    pub m: usize,
+    pub ef_construction: usize,
+    pub seed: u64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            m: 16,
+            ef_construction: 200,
+            seed: 0x5eed,
+        }
+    }
+}
+
+// Hierarchical navigable small world graph (Malkov & Yashunin). Deleted rows
+// are tombstoned: they keep routing searches but are never returned.
+#[derive(Debug, Clone)]
+pub struct Hnsw<F> {
+    space: Space,
+    dim: usize,
+    params: Params,
+    data: Vec<F>,
+    // `links[node][layer]` are the neighbours of `node` on `layer`
+    links: Vec<Vec<Vec<usize>>>,
+    deleted: Vec<bool>,
+    live: usize,
+    entry: Option<usize>,
+    rng: Rng,
+}
+
+impl<F: SimdFloat + Float> Hnsw<F> {
+    pub fn new(dim: usize, space: Space) -> Self {
+        Self::with_params(dim, space, Params::default())
+    }
+
+    pub fn with_params(dim: usize, space: Space, params: Params) -> Self {
+        assert!(dim > 0, "dim must be positive");
+        assert!(params.m > 1, "m must be at least 2");
+        assert!(
+            params.ef_construction > 0,
+            "ef_construction must be positive"
+        );
+        Hnsw {
+            space,
+            dim,
+            params,
+            data: Vec::new(),
+            links: Vec::new(),
+            deleted: Vec::new(),
+            live: 0,
+            entry: None,
+            rng: Rng::new(params.seed),
+        }
+    }
+
+    // Number of rows that have not been deleted
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    fn row(&self, index: usize) -> &[F] {
+        &self.data[index * self.dim..(index + 1) * self.dim]
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            2 * self.params.m
+        } else {
+            self.params.m
+        }
+    }
+
+    // Geometric level with ratio 1/m
+    fn random_level(&mut self) -> usize {
+        (-self.rng.uniform().ln() / (self.params.m as f64).ln()) as usize
+    }
+
+    // Inserts a row and returns its index. Indices are assigned in insertion
+    // order and are not reused after a deletion.
+    pub fn insert(&mut self, vector: &[F]) -> usize {
+        assert_eq!(vector.len(), self.dim, "vector length does not match dim");
+        let index = self.deleted.len();
+        let level = self.random_level();
+        self.data.extend_from_slice(vector);
+        self.links.push(vec![Vec::new(); level + 1]);
+        self.deleted.push(false);
+        self.live += 1;
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(index);
+            return index;
+        };
+        let top = self.links[entry].len() - 1;
+
+        let mut entries = vec![Candidate {
+            key: self.space.key(vector, self.row(entry)),
+            index: entry,
+        }];
+        for layer in (level + 1..=top).rev() {
+            entries = self.search_layer(vector, entries, 1, layer);
+        }
+        for layer in (0..=level.min(top)).rev() {
+            entries = self.search_layer(vector, entries, self.params.ef_construction, layer);
+            let neighbours = self.select(&entries, self.params.m);
+            for &neighbour in &neighbours {
+                self.links[neighbour][layer].push(index);
+                if self.links[neighbour][layer].len() > self.max_links(layer) {
+                    self.shrink(neighbour, layer);
+                }
+            }
+            self.links[index][layer] = neighbours;
+        }
+
+        if level > top {
+            self.entry = Some(index);
+        }
+        index
+    }
+
+    // Tombstones a row, returning whether it was present and not yet deleted
+    pub fn delete(&mut self, index: usize) -> bool {
+        match self.deleted.get_mut(index) {
+            Some(deleted) if !*deleted => {
+                *deleted = true;
+                self.live -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // The `k` best rows for `query`, best first, exploring `ef` candidates on
+    // the bottom layer. A larger `ef` trades speed for recall. Deleted rows
+    // take up candidate slots, so `ef` should grow with the share of deletions.
+    pub fn search(&self, query: &[F], k: usize, ef: usize) -> Vec<Neighbour<F>> {
+        assert_eq!(query.len(), self.dim, "query length does not match dim");
+        let Some(entry) = self.entry else {
+            return Vec::new();
+        };
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut entries = vec![Candidate {
+            key: self.space.key(query, self.row(entry)),
+            index: entry,
+        }];
+        for layer in (1..self.links[entry].len()).rev() {
+            entries = self.search_layer(query, entries, 1, layer);
+        }
+        self.search_layer(query, entries, ef.max(k), 0)
+            .into_iter()
+            .filter(|c| !self.deleted[c.index])
+            .take(k)
+            .map(|c| Neighbour {
+                index: c.index,
+                score: self.space.score(c.key),
+            })
+            .collect()
+    }
+
+    // Best-first search of one layer, returning up to `ef` candidates closest
+    // first
+    fn search_layer(
+        &self,
+        query: &[F],
+        entries: Vec<Candidate<F>>,
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate<F>> {
+        let mut visited: HashSet<usize> = entries.iter().map(|c| c.index).collect();
+        let mut candidates: BinaryHeap<Reverse<Candidate<F>>> = BinaryHeap::new();
+        let mut found: BinaryHeap<Candidate<F>> = BinaryHeap::new();
+        for entry in entries {
+            candidates.push(Reverse(entry));
+            found.push(entry);
+        }
+        while found.len() > ef {
+            found.pop();
+        }
+
+        while let Some(Reverse(closest)) = candidates.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|worst| closest > *worst) {
+                break;
+            }
+            for &neighbour in &self.links[closest.index][layer] {
+                if !visited.insert(neighbour) {
+                    continue;
+                }
+                let candidate = Candidate {
+                    key: self.space.key(query, self.row(neighbour)),
+                    index: neighbour,
+                };
+                if found.len() < ef || found.peek().is_some_and(|worst| candidate < *worst) {
+                    candidates.push(Reverse(candidate));
+                    found.push(candidate);
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        found.into_sorted_vec()
+    }
+
+    // Neighbour selection heuristic: a candidate is kept only if it is closer
+    // to the new node than to every neighbour kept so far, which spreads the
+    // links in different directions. Pruned candidates fill any free slots.
+    fn select(&self, candidates: &[Candidate<F>], m: usize) -> Vec<usize> {
+        let mut kept: Vec<usize> = Vec::with_capacity(m);
+        let mut pruned = Vec::new();
+        for candidate in candidates {
+            if kept.len() == m {
+                break;
+            }
+            let row = self.row(candidate.index);
+            if kept
+                .iter()
+                .all(|&other| candidate.key < self.space.key(row, self.row(other)))
+            {
+                kept.push(candidate.index);
+            } else {
+                pruned.push(candidate.index);
+            }
+        }
+        let free = m - kept.len();
+        kept.extend(pruned.into_iter().take(free));
+        kept
+    }
+
+    fn shrink(&mut self, node: usize, layer: usize) {
+        let row = self.row(node);
+        let mut candidates: Vec<Candidate<F>> = self.links[node][layer]
+            .iter()
+            .map(|&index| Candidate {
+                key: self.space.key(row, self.row(index)),
+                index,
+            })
+            .collect();
+        candidates.sort();
+        self.links[node][layer] = self.select(&candidates, self.max_links(layer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hnsw::{Hnsw, Params, Space};
+    use crate::rng::samples;
+    use crate::{distance, knn, similarity};
+
+    // Uniform in (-0.5, 0.5]
+    fn dataset(n: usize, dim: usize, seed: u64) -> Vec<f32> {
+        samples(n * dim, seed, |rng| rng.uniform() as f32 - 0.5)
+    }
+
+    fn build(data: &[f32], dim: usize, space: Space) -> Hnsw<f32> {
+        let params = Params {
+            m: 12,
+            ef_construction: 100,
+            ..Params::default()
+        };
+        let mut index = Hnsw::with_params(dim, space, params);
+        for row in data.chunks(dim) {
+            index.insert(row);
+        }
+        index
+    }
+
+    #[test]
+    fn recall_against_brute_force() {
+        let (n, dim, k) = (1000, 16, 10);
+        let data = dataset(n, dim, 1);
+        let queries = dataset(50, dim, 2);
+
+        for space in [Space::Cosine, Space::InnerProduct, Space::Euclidean] {
+            let index = build(&data, dim, space);
+            let mut hits = 0;
+            for query in queries.chunks(dim) {
+                let exact = match space {
+                    Space::Cosine => knn::knn_similarity(query, &data, dim, k, similarity::cosine),
+                    Space::InnerProduct => {
+                        knn::knn_similarity(query, &data, dim, k, distance::inner_product)
+                    }
+                    Space::Euclidean => knn::knn(query, &data, dim, k, distance::euclidean),
+                };
+                let found = index.search(query, k, 64);
+                assert_eq!(found.len(), k);
+                hits += found
+                    .iter()
+                    .filter(|n| exact.iter().any(|e| e.index == n.index))
+                    .count();
+            }
+            let recall = hits as f64 / (queries.len() / dim * k) as f64;
+            assert!(recall >= 0.95, "{space:?} recall {recall}");
+        }
+    }
+
+    #[test]
+    fn scores_are_the_measure() {
+        let data = dataset(100, 8, 3);
+        let query = &data[..8];
+        let index = build(&data, 8, Space::Euclidean);
+        let best = index.search(query, 2, 32);
+        assert_eq!(best[0].index, 0);
+        assert_eq!(best[0].score, 0.0);
+        assert_relative_eq!(
+            best[1].score,
+            distance::euclidean(query, &data[best[1].index * 8..][..8]),
+            max_relative = 1e-5
+        );
+
+        let index = build(&data, 8, Space::Cosine);
+        let best = index.search(query, 1, 32);
+        assert_relative_eq!(best[0].score, 1.0, max_relative = 1e-5);
+    }
+
+    #[test]
+    fn deterministic_under_seed() {
+        let data = dataset(500, 8, 4);
+        let queries = dataset(20, 8, 5);
+        let (a, b) = (
+            build(&data, 8, Space::Cosine),
+            build(&data, 8, Space::Cosine),
+        );
+        assert_eq!(a.links, b.links);
+        for query in queries.chunks(8) {
+            assert_eq!(a.search(query, 5, 16), b.search(query, 5, 16));
+        }
+    }
+
+    #[test]
+    fn deleted_rows_are_skipped() {
+        let data = dataset(300, 4, 6);
+        let mut index = build(&data, 4, Space::Euclidean);
+        let query = &data[40..44];
+        assert_eq!(index.search(query, 1, 16)[0].index, 10);
+
+        assert!(index.delete(10));
+        assert!(!index.delete(10));
+        assert!(!index.delete(300));
+        assert_eq!(index.len(), 299);
+        let found = index.search(query, 5, 32);
+        assert_eq!(found.len(), 5);
+        assert!(found.iter().all(|n| n.index != 10));
+    }
+
+    #[test]
+    fn empty_index() {
+        let index = Hnsw::<f64>::new(3, Space::InnerProduct);
+        assert!(index.is_empty());
+        assert!(index.search(&[1.0, 0.0, 0.0], 3, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "ef_construction must be positive")]
+    fn zero_ef_construction() {
+        let params = Params {
+            ef_construction: 0,
+            ..Params::default()
+        };
+        Hnsw::<f32>::with_params(4, Space::Euclidean, params);
+    }
+}
@@ -0,0 +1,281 @@
+use num_traits::Float;
+
+use crate::distance;
+use crate::element::Element;
+use crate::knn::{sort_neighbours, Nearest, Neighbour};
+use crate::pairwise::rows;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+    Minkowski(f64),
+}
+
+impl Metric {
+    pub fn distance<F: Element<Float = F>>(&self, p: &[F], q: &[F]) -> F {
+        match *self {
+            Metric::Euclidean => distance::euclidean(p, q),
+            Metric::Manhattan => distance::manhattan(p, q),
+            Metric::Chebyshev => distance::chebyshev(p, q),
+            Metric::Minkowski(d) => distance::minkowski(p, q, d),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node<F> {
+    Leaf {
+        start: usize,
+        end: usize,
+    },
+    Split {
+        axis: usize,
+        value: F,
+        left: usize,
+        right: usize,
+    },
+}
+
+// KD-tree over the rows of a flat row-major buffer. Pruning relies on every
+// Minkowski distance being at least the gap along a single coordinate.
+#[derive(Debug, Clone)]
+pub struct KdTree<F> {
+    data: Vec<F>,
+    dim: usize,
+    metric: Metric,
+    indices: Vec<usize>,
+    nodes: Vec<Node<F>>,
+}
+
+const LEAF_SIZE: usize = 16;
+
+impl<F: Float + Element<Float = F>> KdTree<F> {
+    pub fn new(data: &[F], dim: usize, metric: Metric) -> Self {
+        Self::with_leaf_size(data, dim, metric, LEAF_SIZE)
+    }
+
+    pub fn with_leaf_size(data: &[F], dim: usize, metric: Metric, leaf_size: usize) -> Self {
+        let n = rows(data, dim).len();
+        let mut tree = KdTree {
+            data: data.to_vec(),
+            dim,
+            metric,
+            indices: (0..n).collect(),
+            nodes: Vec::new(),
+        };
+        if n > 0 {
+            tree.build(0, n, leaf_size.max(1));
+        }
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    fn row(&self, index: usize) -> &[F] {
+        &self.data[index * self.dim..(index + 1) * self.dim]
+    }
+
+    fn coordinate(&self, index: usize, axis: usize) -> F {
+        self.data[index * self.dim + axis]
+    }
+
+    fn build(&mut self, start: usize, end: usize, leaf_size: usize) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(Node::Leaf { start, end });
+        if end - start <= leaf_size {
+            return id;
+        }
+
+        // Split on the median of the widest coordinate
+        let axis = (0..self.dim)
+            .map(|axis| {
+                let (min, max) = self.indices[start..end].iter().fold(
+                    (F::infinity(), F::neg_infinity()),
+                    |(min, max), &i| {
+                        let v = self.coordinate(i, axis);
+                        (min.min(v), max.max(v))
+                    },
+                );
+                (axis, max - min)
+            })
+            .fold((0, F::neg_infinity()), |best, (axis, spread)| {
+                if spread > best.1 {
+                    (axis, spread)
+                } else {
+                    best
+                }
+            })
+            .0;
+
+        let mid = start + (end - start) / 2;
+        let (data, dim) = (&self.data, self.dim);
+        self.indices[start..end].select_nth_unstable_by(mid - start, |&a, &b| {
+            data[a * dim + axis]
+                .partial_cmp(&data[b * dim + axis])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let value = self.coordinate(self.indices[mid], axis);
+
+        let left = self.build(start, mid, leaf_size);
+        let right = self.build(mid, end, leaf_size);
+        self.nodes[id] = Node::Split {
+            axis,
+            value,
+            left,
+            right,
+        };
+        id
+    }
+
+    // The `k` nearest rows, closest first, ties broken by the lowest index
+    pub fn knn(&self, query: &[F], k: usize) -> Vec<Neighbour<F>> {
+        assert_eq!(query.len(), self.dim, "query length does not match dim");
+        let mut nearest = Nearest::new(k);
+        if k > 0 && !self.is_empty() {
+            self.search_knn(0, query, &mut nearest);
+        }
+        nearest.into_neighbours()
+    }
+
+    fn search_knn(&self, node: usize, query: &[F], nearest: &mut Nearest<F>) {
+        match self.nodes[node] {
+            Node::Leaf { start, end } => {
+                for &index in &self.indices[start..end] {
+                    nearest.push(index, self.metric.distance(query, self.row(index)));
+                }
+            }
+            Node::Split {
+                axis,
+                value,
+                left,
+                right,
+            } => {
+                let gap = query[axis] - value;
+                let (near, far) = if gap < F::zero() {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                self.search_knn(near, query, nearest);
+                // Equal bounds are still visited so that ties resolve by index
+                if nearest.bound().is_none_or(|bound| gap.abs() <= bound) {
+                    self.search_knn(far, query, nearest);
+                }
+            }
+        }
+    }
+
+    // Every row within `radius` (inclusive), closest first
+    pub fn radius(&self, query: &[F], radius: F) -> Vec<Neighbour<F>> {
+        assert_eq!(query.len(), self.dim, "query length does not match dim");
+        let mut neighbours = Vec::new();
+        if !self.is_empty() {
+            self.search_radius(0, query, radius, &mut neighbours);
+        }
+        sort_neighbours(&mut neighbours);
+        neighbours
+    }
+
+    fn search_radius(&self, node: usize, query: &[F], radius: F, out: &mut Vec<Neighbour<F>>) {
+        match self.nodes[node] {
+            Node::Leaf { start, end } => {
+                for &index in &self.indices[start..end] {
+                    let score = self.metric.distance(query, self.row(index));
+                    if score <= radius {
+                        out.push(Neighbour { index, score });
+                    }
+                }
+            }
+            Node::Split {
+                axis,
+                value,
+                left,
+                right,
+            } => {
+                let gap = query[axis] - value;
+                if gap <= radius {
+                    self.search_radius(left, query, radius, out);
+                }
+                if -gap <= radius {
+                    self.search_radius(right, query, radius, out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kdtree::{KdTree, Metric};
+    use crate::rng::samples;
+    use crate::{distance, knn};
+
+    // Coarse values so that exact ties actually occur
+    fn dataset(n: usize, dim: usize, seed: u64) -> Vec<f64> {
+        samples(n * dim, seed, |rng| rng.below(50) as f64 / 5.0)
+    }
+
+    const METRICS: [Metric; 4] = [
+        Metric::Euclidean,
+        Metric::Manhattan,
+        Metric::Chebyshev,
+        Metric::Minkowski(3.0),
+    ];
+
+    #[test]
+    fn knn_matches_brute_force() {
+        for dim in [2, 3, 7, 20] {
+            let data = dataset(500, dim, dim as u64);
+            let queries = dataset(40, dim, 100 + dim as u64);
+
+            for metric in METRICS {
+                let tree = KdTree::with_leaf_size(&data, dim, metric, 4);
+                for query in queries.chunks(dim) {
+                    for k in [1, 5, 20] {
+                        let expected = knn::knn(query, &data, dim, k, |p, q| metric.distance(p, q));
+                        assert_eq!(tree.knn(query, k), expected);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn radius_matches_brute_force() {
+        for dim in [2, 3, 7] {
+            let data = dataset(400, dim, 7 * dim as u64);
+            let queries = dataset(30, dim, 200 + dim as u64);
+
+            for metric in METRICS {
+                let tree = KdTree::new(&data, dim, metric);
+                for query in queries.chunks(dim) {
+                    let expected =
+                        knn::radius(query, &data, dim, 3.0, |p, q| metric.distance(p, q));
+                    assert_eq!(tree.radius(query, 3.0), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn f32_and_edge_cases() {
+        let data: Vec<f32> = dataset(50, 2, 1).iter().map(|&v| v as f32).collect();
+        let tree = KdTree::new(&data, 2, Metric::Euclidean);
+        let expected = knn::knn(&[1.0f32, 2.0], &data, 2, 3, distance::euclidean);
+        assert_eq!(tree.knn(&[1.0, 2.0], 3), expected);
+        assert!(tree.knn(&[1.0, 2.0], 0).is_empty());
+        assert_eq!(tree.knn(&[1.0, 2.0], 100).len(), 50);
+
+        let empty = KdTree::<f64>::new(&[], 3, Metric::Manhattan);
+        assert!(empty.knn(&[0.0, 0.0, 0.0], 2).is_empty());
+        assert!(empty.radius(&[0.0, 0.0, 0.0], 1.0).is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use num_traits::Float;
+
+use crate::pairwise::{check_dim, rows};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbour<R> {
+    pub index: usize,
+    pub score: R,
+}
+
+// NaN scores rank after every other value
+fn compare<R: Float>(a: R, b: R) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => a.partial_cmp(&b).unwrap(),
+        (a_nan, b_nan) => a_nan.cmp(&b_nan),
+    }
+}
+
+// Heap entry ordered by `(key, index)`, smaller is better
+#[derive(Clone, Copy)]
+pub(crate) struct Candidate<R> {
+    pub(crate) key: R,
+    pub(crate) index: usize,
+}
+
+impl<R: Float> Ord for Candidate<R> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(self.key, other.key).then(self.index.cmp(&other.index))
+    }
+}
+
+impl<R: Float> PartialOrd for Candidate<R> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<R: Float> PartialEq for Candidate<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<R: Float> Eq for Candidate<R> {}
+
+// Bounded max-heap keeping the `k` smallest `(key, index)` pairs seen so far
+pub(crate) struct Nearest<R> {
+    k: usize,
+    heap: BinaryHeap<Candidate<R>>,
+}
+
+impl<R: Float> Nearest<R> {
+    pub(crate) fn new(k: usize) -> Self {
+        Nearest {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    pub(crate) fn push(&mut self, index: usize, key: R) {
+        let candidate = Candidate { key, index };
+        if self.heap.len() < self.k {
+            self.heap.push(candidate);
+        } else if self.heap.peek().is_some_and(|worst| candidate < *worst) {
+            self.heap.pop();
+            self.heap.push(candidate);
+        }
+    }
+
+    // Largest key kept once the heap is full: anything farther cannot enter
+    pub(crate) fn bound(&self) -> Option<R> {
+        if self.heap.len() < self.k {
+            None
+        } else {
+            self.heap.peek().map(|worst| worst.key)
+        }
+    }
+
+    pub(crate) fn into_neighbours(self) -> Vec<Neighbour<R>> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|c| Neighbour {
+                index: c.index,
+                score: c.key,
+            })
+            .collect()
+    }
+}
+
+// Orders neighbours by increasing score, then by index
+pub(crate) fn sort_neighbours<R: Float>(neighbours: &mut [Neighbour<R>]) {
+    neighbours.sort_by(|a, b| compare(a.score, b.score).then(a.index.cmp(&b.index)));
+}
+
+// The `k` rows of `data` closest to `query`, closest first. Ties are broken by
+// the lowest index.
+pub fn knn<T, R, F>(query: &[T], data: &[T], dim: usize, k: usize, measure: F) -> Vec<Neighbour<R>>
+where
+    R: Float,
+    F: Fn(&[T], &[T]) -> R,
+{
+    assert_eq!(query.len(), dim, "query length does not match dim");
+    let mut nearest = Nearest::new(k);
+    for (index, row) in rows(data, dim).enumerate() {
+        nearest.push(index, measure(query, row));
+    }
+
+    nearest.into_neighbours()
+}
+
+// The `k` rows of `data` most similar to `query`, most similar first
+pub fn knn_similarity<T, R, F>(
+    query: &[T],
+    data: &[T],
+    dim: usize,
+    k: usize,
+    measure: F,
+) -> Vec<Neighbour<R>>
+where
+    R: Float,
+    F: Fn(&[T], &[T]) -> R,
+{
+    assert_eq!(query.len(), dim, "query length does not match dim");
+    let mut nearest = Nearest::new(k);
+    for (index, row) in rows(data, dim).enumerate() {
+        nearest.push(index, -measure(query, row));
+    }
+
+    nearest
+        .into_neighbours()
+        .into_iter()
+        .map(|n| Neighbour {
+            index: n.index,
+            score: -n.score,
+        })
+        .collect()
+}
+
+// Every row within `radius` (inclusive) of `query`, closest first
+pub fn radius<T, R, F>(
+    query: &[T],
+    data: &[T],
+    dim: usize,
+    radius: R,
+    measure: F,
+) -> Vec<Neighbour<R>>
+where
+    R: Float,
+    F: Fn(&[T], &[T]) -> R,
+{
+    assert_eq!(query.len(), dim, "query length does not match dim");
+    let mut neighbours: Vec<_> = rows(data, dim)
+        .map(|row| measure(query, row))
+        .enumerate()
+        .filter(|&(_, score)| score <= radius)
+        .map(|(index, score)| Neighbour { index, score })
+        .collect();
+
+    sort_neighbours(&mut neighbours);
+    neighbours
+}
+
+// Every row whose similarity to `query` is at least `threshold`, most similar first
+pub fn radius_similarity<T, R, F>(
+    query: &[T],
+    data: &[T],
+    dim: usize,
+    threshold: R,
+    measure: F,
+) -> Vec<Neighbour<R>>
+where
+    R: Float,
+    F: Fn(&[T], &[T]) -> R,
+{
+    assert_eq!(query.len(), dim, "query length does not match dim");
+    let mut neighbours: Vec<_> = rows(data, dim)
+        .map(|row| measure(query, row))
+        .enumerate()
+        .filter(|&(_, score)| score >= threshold)
+        .map(|(index, score)| Neighbour { index, score })
+        .collect();
+
+    neighbours.sort_by(|a, b| compare(b.score, a.score).then(a.index.cmp(&b.index)));
+    neighbours
+}
+
+// `knn` for every row of `queries`
+pub fn knn_batch<T, R, F>(
+    queries: &[T],
+    data: &[T],
+    dim: usize,
+    k: usize,
+    measure: F,
+) -> Vec<Vec<Neighbour<R>>>
+where
+    R: Float,
+    F: Fn(&[T], &[T]) -> R,
+{
+    check_dim(data, dim);
+    rows(queries, dim)
+        .map(|query| knn(query, data, dim, k, &measure))
+        .collect()
+}
+
+// `knn_similarity` for every row of `queries`
+pub fn knn_similarity_batch<T, R, F>(
+    queries: &[T],
+    data: &[T],
+    dim: usize,
+    k: usize,
+    measure: F,
+) -> Vec<Vec<Neighbour<R>>>
+where
+    R: Float,
+    F: Fn(&[T], &[T]) -> R,
+{
+    check_dim(data, dim);
+    rows(queries, dim)
+        .map(|query| knn_similarity(query, data, dim, k, &measure))
+        .collect()
+}
+
+#[cfg(feature = "rayon")]
+pub fn par_knn_batch<T, R, F>(
+    queries: &[T],
+    data: &[T],
+    dim: usize,
+    k: usize,
+    measure: F,
+) -> Vec<Vec<Neighbour<R>>>
+where
+    T: Sync,
+    R: Float + Send,
+    F: Fn(&[T], &[T]) -> R + Sync,
+{
+    check_dim(queries, dim);
+    check_dim(data, dim);
+    queries
+        .par_chunks_exact(dim)
+        .map(|query| knn(query, data, dim, k, &measure))
+        .collect()
+}
+
+#[cfg(feature = "rayon")]
+pub fn par_knn_similarity_batch<T, R, F>(
+    queries: &[T],
+    data: &[T],
+    dim: usize,
+    k: usize,
+    measure: F,
+) -> Vec<Vec<Neighbour<R>>>
+where
+    T: Sync,
+    R: Float + Send,
+    F: Fn(&[T], &[T]) -> R + Sync,
+{
+    check_dim(queries, dim);
+    check_dim(data, dim);
+    queries
+        .par_chunks_exact(dim)
+        .map(|query| knn_similarity(query, data, dim, k, &measure))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{distance, knn, similarity};
+
+    const DATA: [f64; 12] = [
+        0.0, 0.0, // 0
+        3.0, 0.0, // 1
+        1.0, 1.0, // 2
+        0.0, 3.0, // 3
+        1.0, -1.0, // 4
+        -2.0, 0.0, // 5
+    ];
+
+    fn indices<R>(neighbours: &[knn::Neighbour<R>]) -> Vec<usize> {
+        neighbours.iter().map(|n| n.index).collect()
+    }
+
+    #[test]
+    fn knn() {
+        let result = knn::knn(&[1.0, 0.2], &DATA, 2, 3, distance::euclidean);
+        assert_eq!(indices(&result), vec![2, 0, 4]);
+        assert_relative_eq!(result[0].score, 0.8, epsilon = 1e-9);
+
+        let all = knn::knn(&[0.0, 0.0], &DATA, 2, 10, distance::manhattan);
+        assert_eq!(all.len(), 6);
+    }
+
+    #[test]
+    fn ties_break_by_index() {
+        // 2, 4 and 5 are all at manhattan distance 2
+        let result = knn::knn(&[0.0, 0.0], &DATA, 2, 3, distance::manhattan);
+        assert_eq!(indices(&result), vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn knn_similarity() {
+        let result = knn::knn_similarity(&[1.0, 0.1], &DATA[2..], 2, 2, similarity::cosine);
+        assert_eq!(indices(&result), vec![0, 1]);
+        assert!(result[0].score > result[1].score);
+    }
+
+    #[test]
+    fn radius() {
+        let result = knn::radius(&[0.0, 0.0], &DATA, 2, 2.0, distance::euclidean);
+        assert_eq!(indices(&result), vec![0, 2, 4, 5]);
+
+        let result = knn::radius_similarity(&[1.0, 0.0], &DATA[2..], 2, 0.7, similarity::cosine);
+        assert_eq!(indices(&result), vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn batch() {
+        let queries = [0.0, 0.0, 3.0, 0.1];
+        let result = knn::knn_batch(&queries, &DATA, 2, 2, distance::euclidean);
+        assert_eq!(result.len(), 2);
+        assert_eq!(indices(&result[0]), vec![0, 2]);
+        assert_eq!(indices(&result[1]), vec![1, 2]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_batch() {
+        let queries = [0.0, 0.0, 3.0, 0.1, -1.0, 2.0];
+        assert_eq!(
+            knn::par_knn_batch(&queries, &DATA, 2, 3, distance::euclidean),
+            knn::knn_batch(&queries, &DATA, 2, 3, distance::euclidean)
+        );
+    }
+}
@@ -3,5 +3,31 @@
 #[macro_use]
 extern crate approx;
 
+mod element;
+mod error;
+mod measure;
+mod rng;
+
+pub mod balltree;
+pub mod binary;
+pub mod bitset;
+pub mod covariance;
 pub mod distance;
+pub mod gower;
+pub mod hnsw;
+pub mod kdtree;
+pub mod knn;
+pub mod lsh;
+pub mod nan;
+pub mod pairwise;
+pub mod pq;
+pub mod quantized;
+pub mod simd;
 pub mod similarity;
+pub mod string;
+pub mod vptree;
+pub mod weighted;
+
+pub use element::Element;
+pub use error::{Error, Result};
+pub use measure::{Distance, Metric, Similarity};
@@ -0,0 +1,400 @@
+// Locality-sensitive hashing. Each family hashes close vectors under its
+// measure to the same bucket with high probability:
+//
+// - `Hyperplane`: random hyperplanes, for `similarity::cosine`
+// - `PStable`: Gaussian (2-stable) or Cauchy (1-stable) projections, for
+//   `distance::euclidean` and `distance::manhattan`
+// - `MinHash`: for the set Jaccard distance over the non-zero entries,
+//   `distance::tanimoto_bitvector`
+// - `BitSampling`: for `distance::hamming`
+//
+// An `Index` keeps one hash table per hasher and re-ranks the union of the
+// matching buckets with the exact measure.
+
+use std::collections::HashMap;
+
+use num_traits::{Float, ToPrimitive, Zero};
+
+use crate::element::Element;
+use crate::knn::{Nearest, Neighbour};
+use crate::pairwise::rows;
+use crate::rng::{mix, Rng};
+
+pub trait Hasher {
+    // Bucket of `vector` in the table of this hasher
+    fn hash<T: Element>(&self, vector: &[T]) -> u64;
+}
+
+fn to_f64<T: Element>(value: T) -> f64 {
+    value.to_float().to_f64().unwrap()
+}
+
+fn dot<T: Element>(vector: &[T], projection: &[f64]) -> f64 {
+    vector
+        .iter()
+        .zip(projection)
+        .map(|(&v, &a)| to_f64(v) * a)
+        .sum()
+}
+
+// Folds the per-function values into a single bucket key
+fn combine(values: impl Iterator<Item = u64>) -> u64 {
+    values.fold(0, |key, value| mix(key.rotate_left(23) ^ value))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hyperplane {
+    dim: usize,
+    normals: Vec<f64>,
+}
+
+impl Hyperplane {
+    // `bits` random hyperplanes through the origin, one bit of the key each
+    pub fn new(dim: usize, bits: usize, seed: u64) -> Self {
+        assert!(bits <= 64, "at most 64 hyperplanes fit in a key");
+        let mut rng = Rng::new(seed);
+        Hyperplane {
+            dim,
+            normals: (0..dim * bits).map(|_| rng.gaussian()).collect(),
+        }
+    }
+}
+
+impl Hasher for Hyperplane {
+    fn hash<T: Element>(&self, vector: &[T]) -> u64 {
+        assert_eq!(vector.len(), self.dim, "vector length does not match dim");
+        self.normals
+            .chunks(self.dim)
+            .enumerate()
+            .filter(|(_, normal)| dot(vector, normal) >= 0.0)
+            .fold(0, |key, (bit, _)| key | 1 << bit)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PStable {
+    dim: usize,
+    width: f64,
+    projections: Vec<f64>,
+    offsets: Vec<f64>,
+}
+
+impl PStable {
+    // `floor((a . x + b) / width)` with Gaussian `a`, for `euclidean`
+    pub fn euclidean(dim: usize, functions: usize, width: f64, seed: u64) -> Self {
+        Self::with_distribution(dim, functions, width, seed, Rng::gaussian)
+    }
+
+    // `floor((a . x + b) / width)` with Cauchy `a`, for `manhattan`
+    pub fn manhattan(dim: usize, functions: usize, width: f64, seed: u64) -> Self {
+        Self::with_distribution(dim, functions, width, seed, Rng::cauchy)
+    }
+
+    fn with_distribution(
+        dim: usize,
+        functions: usize,
+        width: f64,
+        seed: u64,
+        sample: fn(&mut Rng) -> f64,
+    ) -> Self {
+        assert!(width > 0.0, "bucket width must be positive");
+        let mut rng = Rng::new(seed);
+        let projections = (0..dim * functions).map(|_| sample(&mut rng)).collect();
+        let offsets = (0..functions).map(|_| rng.uniform() * width).collect();
+        PStable {
+            dim,
+            width,
+            projections,
+            offsets,
+        }
+    }
+}
+
+impl Hasher for PStable {
+    fn hash<T: Element>(&self, vector: &[T]) -> u64 {
+        assert_eq!(vector.len(), self.dim, "vector length does not match dim");
+        combine(
+            self.projections
+                .chunks(self.dim)
+                .zip(&self.offsets)
+                .map(|(a, b)| ((dot(vector, a) + b) / self.width).floor() as i64 as u64),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinHash {
+    seeds: Vec<u64>,
+}
+
+impl MinHash {
+    // `functions` independent hashes of the positions of the non-zero entries
+    pub fn new(functions: usize, seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        MinHash {
+            seeds: (0..functions).map(|_| rng.next_u64()).collect(),
+        }
+    }
+
+    // The minimum of every hash function over the set. Two sets agree on each
+    // entry with probability equal to their Jaccard similarity.
+    pub fn signature<T: Element>(&self, vector: &[T]) -> Vec<u64> {
+        let members: Vec<u64> = vector
+            .iter()
+            .enumerate()
+            .filter(|(_, &v)| !v.to_float().is_zero())
+            .map(|(i, _)| i as u64)
+            .collect();
+        self.seeds
+            .iter()
+            .map(|&seed| {
+                members
+                    .iter()
+                    .map(|&i| mix(seed ^ mix(i)))
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect()
+    }
+}
+
+impl Hasher for MinHash {
+    fn hash<T: Element>(&self, vector: &[T]) -> u64 {
+        combine(self.signature(vector).into_iter())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitSampling {
+    dim: usize,
+    positions: Vec<usize>,
+}
+
+impl BitSampling {
+    // The values at `functions` random positions
+    pub fn new(dim: usize, functions: usize, seed: u64) -> Self {
+        assert!(dim > 0, "dim must be positive");
+        let mut rng = Rng::new(seed);
+        BitSampling {
+            dim,
+            positions: (0..functions).map(|_| rng.below(dim)).collect(),
+        }
+    }
+}
+
+impl Hasher for BitSampling {
+    fn hash<T: Element>(&self, vector: &[T]) -> u64 {
+        assert_eq!(vector.len(), self.dim, "vector length does not match dim");
+        combine(self.positions.iter().map(|&i| to_f64(vector[i]).to_bits()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Index<T, H> {
+    dim: usize,
+    data: Vec<T>,
+    tables: Vec<(H, HashMap<u64, Vec<usize>>)>,
+}
+
+impl<T: Element, H: Hasher> Index<T, H> {
+    // One table per hasher. More tables raise recall, more functions per
+    // hasher make the buckets more selective.
+    pub fn new(dim: usize, hashers: Vec<H>) -> Self {
+        assert!(dim > 0, "dim must be positive");
+        assert!(!hashers.is_empty(), "at least one hasher is required");
+        Index {
+            dim,
+            data: Vec::new(),
+            tables: hashers.into_iter().map(|h| (h, HashMap::new())).collect(),
+        }
+    }
+
+    // Indexes every row of `data`
+    pub fn with_data(data: &[T], dim: usize, hashers: Vec<H>) -> Self {
+        let mut index = Self::new(dim, hashers);
+        for row in rows(data, dim) {
+            index.insert(row);
+        }
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / self.dim
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn row(&self, index: usize) -> &[T] {
+        &self.data[index * self.dim..(index + 1) * self.dim]
+    }
+
+    // Adds a row and returns its index
+    pub fn insert(&mut self, vector: &[T]) -> usize {
+        assert_eq!(vector.len(), self.dim, "vector length does not match dim");
+        let index = self.len();
+        self.data.extend_from_slice(vector);
+        for (hasher, table) in &mut self.tables {
+            table.entry(hasher.hash(vector)).or_default().push(index);
+        }
+        index
+    }
+
+    // Indices sharing a bucket with `query` in at least one table, ascending
+    pub fn candidates(&self, query: &[T]) -> Vec<usize> {
+        assert_eq!(query.len(), self.dim, "query length does not match dim");
+        let mut candidates: Vec<usize> = self
+            .tables
+            .iter()
+            .filter_map(|(hasher, table)| table.get(&hasher.hash(query)))
+            .flatten()
+            .copied()
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+
+    // The `k` candidates closest to `query` under the exact distance `measure`
+    pub fn knn<R, F>(&self, query: &[T], k: usize, measure: F) -> Vec<Neighbour<R>>
+    where
+        R: Float,
+        F: Fn(&[T], &[T]) -> R,
+    {
+        let mut nearest = Nearest::new(k);
+        for index in self.candidates(query) {
+            nearest.push(index, measure(query, self.row(index)));
+        }
+        nearest.into_neighbours()
+    }
+
+    // The `k` candidates most similar to `query` under the exact `measure`
+    pub fn knn_similarity<R, F>(&self, query: &[T], k: usize, measure: F) -> Vec<Neighbour<R>>
+    where
+        R: Float,
+        F: Fn(&[T], &[T]) -> R,
+    {
+        self.knn(query, k, |p, q| -measure(p, q))
+            .into_iter()
+            .map(|n| Neighbour {
+                index: n.index,
+                score: -n.score,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lsh::{BitSampling, Hasher, Hyperplane, Index, MinHash, PStable};
+    use crate::rng::samples;
+    use crate::{distance, knn, similarity};
+
+    // Random rows, each followed by a slightly perturbed copy of itself
+    fn pairs(n: usize, dim: usize, noise: f64, seed: u64) -> Vec<f64> {
+        samples(n, seed, |rng| {
+            let row: Vec<f64> = (0..dim).map(|_| rng.gaussian()).collect();
+            let copy: Vec<f64> = row.iter().map(|v| v + noise * rng.gaussian()).collect();
+            [row, copy].concat()
+        })
+        .concat()
+    }
+
+    fn sets(n: usize, dim: usize, flips: usize, seed: u64) -> Vec<f64> {
+        samples(n, seed, |rng| {
+            let row: Vec<f64> = (0..dim)
+                .map(|_| (rng.uniform() < 0.3) as u8 as f64)
+                .collect();
+            let mut copy = row.clone();
+            for _ in 0..flips {
+                let i = rng.below(dim);
+                copy[i] = 1.0 - copy[i];
+            }
+            [row, copy].concat()
+        })
+        .concat()
+    }
+
+    // Every row should find its twin, as brute force does
+    fn finds_twins<H: Hasher>(
+        data: &[f64],
+        dim: usize,
+        hashers: Vec<H>,
+        measure: fn(&[f64], &[f64]) -> f64,
+    ) {
+        let index = Index::with_data(data, dim, hashers);
+        let mut found = 0;
+        for (i, query) in data.chunks(dim).enumerate() {
+            let exact = knn::knn(query, data, dim, 2, measure);
+            let approximate = index.knn(query, 2, measure);
+            assert!(approximate.iter().all(|n| n.score >= exact[0].score));
+            found += usize::from(approximate.get(1).map(|n| n.index) == Some(i ^ 1));
+        }
+        let recall = found as f64 / index.len() as f64;
+        assert!(recall >= 0.9, "recall {recall}");
+    }
+
+    #[test]
+    fn hyperplane() {
+        let data = pairs(200, 32, 0.1, 1);
+        let hashers = (0..8).map(|t| Hyperplane::new(32, 12, t)).collect();
+        finds_twins(&data, 32, hashers, |p, q| 1.0 - similarity::cosine(p, q));
+    }
+
+    #[test]
+    fn p_stable() {
+        let data = pairs(200, 16, 0.05, 2);
+        let hashers = (0..8).map(|t| PStable::euclidean(16, 4, 2.0, t)).collect();
+        finds_twins(&data, 16, hashers, distance::euclidean);
+
+        let hashers = (0..8).map(|t| PStable::manhattan(16, 4, 4.0, t)).collect();
+        finds_twins(&data, 16, hashers, distance::manhattan);
+    }
+
+    #[test]
+    fn min_hash() {
+        let data = sets(200, 64, 2, 3);
+        let hashers = (0..10).map(|t| MinHash::new(3, t)).collect();
+        finds_twins(&data, 64, hashers, distance::tanimoto_bitvector);
+
+        // Signature agreement estimates the Jaccard similarity
+        let hasher = MinHash::new(512, 7);
+        let (p, q) = (&data[..64], &data[128..192]);
+        let (p_sig, q_sig) = (hasher.signature(p), hasher.signature(q));
+        let agreement = p_sig.iter().zip(&q_sig).filter(|(a, b)| a == b).count() as f64 / 512.0;
+        assert!((agreement - (1.0 - distance::tanimoto_bitvector(p, q))).abs() < 0.1);
+    }
+
+    #[test]
+    fn bit_sampling() {
+        let data = sets(200, 64, 2, 4);
+        let hashers = (0..10).map(|t| BitSampling::new(64, 12, t)).collect();
+        finds_twins(&data, 64, hashers, distance::hamming);
+    }
+
+    #[test]
+    fn similarity_ranking() {
+        let data = pairs(50, 8, 0.05, 5);
+        let hashers = (0..4).map(|t| Hyperplane::new(8, 6, t)).collect();
+        let index = Index::with_data(&data, 8, hashers);
+        let result = index.knn_similarity(&data[..8], 2, similarity::cosine);
+        assert_eq!(result[0].index, 0);
+        assert_eq!(result[1].index, 1);
+        assert!(result[0].score >= result[1].score);
+    }
+
+    #[test]
+    fn deterministic_under_seed() {
+        let data = pairs(10, 8, 0.1, 6);
+        let (a, b) = (
+            PStable::euclidean(8, 4, 1.0, 42),
+            PStable::euclidean(8, 4, 1.0, 42),
+        );
+        assert_eq!(a, b);
+        for row in data.chunks(8) {
+            assert_eq!(a.hash(row), b.hash(row));
+        }
+        assert_ne!(Hyperplane::new(8, 8, 1), Hyperplane::new(8, 8, 2));
+    }
+}
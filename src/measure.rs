@@ -0,0 +1,63 @@
+pub trait Distance {
+    fn distance(&self, p: &[f64], q: &[f64]) -> f64;
+
+    fn name(&self) -> &'static str;
+}
+
+// Marker for distances satisfying the triangle inequality, which metric trees
+// rely on to prune. Measures such as `kullback_leibler` or `squared_euclidean`
+// deliberately do not implement it.
+pub trait Metric: Distance {
+    // Parametrised measures can be metrics for some parameters only
+    fn is_metric(&self) -> bool {
+        true
+    }
+}
+
+pub trait Similarity {
+    fn similarity(&self, p: &[f64], q: &[f64]) -> f64;
+
+    fn name(&self) -> &'static str;
+}
+
+// Names are compared case-insensitively and without underscores, so both the
+// JS spelling ("kullbackLeibler") and the Rust one ("kullback_leibler") match.
+pub(crate) fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|&c| c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+// Generates a zero-sized struct implementing `$trait` for every measure, plus
+// the `by_name` lookup of the calling module.
+macro_rules! measures {
+    ($trait:ident, $method:ident; $($name:ident => $measure:ident),* $(,)?) => {
+        $(
+            #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+            pub struct $name;
+
+            impl $crate::$trait for $name {
+                fn $method(&self, p: &[f64], q: &[f64]) -> f64 {
+                    $measure(p, q)
+                }
+
+                fn name(&self) -> &'static str {
+                    stringify!($measure)
+                }
+            }
+        )*
+
+        pub fn by_name(name: &str) -> Option<Box<dyn $crate::$trait>> {
+            let name = $crate::measure::normalize(name);
+            $(
+                if name == $crate::measure::normalize(stringify!($measure)) {
+                    return Some(Box::new($name));
+                }
+            )*
+            None
+        }
+    };
+}
+
+pub(crate) use measures;
@@ -0,0 +1,270 @@
+// Missing-value aware versions of the common measures: a coordinate that is
+// NaN in either input is skipped. Sums are rescaled by `n / observed`, the
+// weighting of scikit-learn's `nan_euclidean_distances`, so that a partially
+// observed pair is comparable with a complete one. `chebyshev`, `gower` and
+// `cosine` do not grow with the number of coordinates and are only computed
+// over the observed ones. Without any observed coordinate the result is NaN,
+// and the `try_*` versions return `Error::NothingObserved`.
+
+use num_traits::{Float, Zero};
+
+use crate::element::{lit, Element};
+use crate::error::{check_overflow, check_result, fallible, Error, Result};
+
+// Folds the pairs where neither coordinate is missing, counting them
+fn fold_observed<T, A, F>(p: &[T], q: &[T], init: A, mut f: F) -> (A, usize)
+where
+    T: Element,
+    F: FnMut(A, (T::Float, T::Float)) -> A,
+{
+    p.iter()
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
+        .filter(|(p_i, q_i)| !p_i.is_nan() && !q_i.is_nan())
+        .fold((init, 0), |(acc, observed), pair| {
+            (f(acc, pair), observed + 1)
+        })
+}
+
+// `sum * n / observed`, or NaN when nothing was observed
+fn rescale<F: Float>(sum: F, n: usize, observed: usize) -> F {
+    if observed == 0 {
+        return F::nan();
+    }
+    sum * lit(n as f64 / observed as f64)
+}
+
+fn sum_observed<T: Element>(
+    p: &[T],
+    q: &[T],
+    term: impl Fn(T::Float, T::Float) -> T::Float,
+) -> T::Float {
+    let (sum, observed) = fold_observed(p, q, T::Float::zero(), |sum, (p_i, q_i)| {
+        sum + term(p_i, q_i)
+    });
+    rescale(sum, p.len(), observed)
+}
+
+pub fn euclidean<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    sum_observed(p, q, |p_i, q_i| (p_i - q_i).powi(2)).sqrt()
+}
+
+pub fn manhattan<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    sum_observed(p, q, |p_i, q_i| (p_i - q_i).abs())
+}
+
+pub fn minkowski<T: Element>(p: &[T], q: &[T], d: f64) -> T::Float {
+    sum_observed(p, q, |p_i, q_i| (p_i - q_i).abs().powf(lit(d))).powf(lit(1.0 / d))
+}
+
+pub fn chebyshev<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (max, observed) = fold_observed(p, q, T::Float::zero(), |max, (p_i, q_i)| {
+        max.max((p_i - q_i).abs())
+    });
+    if observed == 0 {
+        return T::Float::nan();
+    }
+    max
+}
+
+pub fn canberra<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    sum_observed(p, q, |p_i, q_i| (p_i - q_i).abs() / (p_i.abs() + q_i.abs()))
+}
+
+// Mean absolute difference over the observed coordinates
+pub fn gower<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (sum, observed) = fold_observed(p, q, T::Float::zero(), |sum, (p_i, q_i)| {
+        sum + (p_i - q_i).abs()
+    });
+    sum / lit(observed as f64)
+}
+
+// Cosine similarity of the observed coordinates
+pub fn cosine<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let zero = T::Float::zero();
+    let ((pq, pp, qq), _) = fold_observed(p, q, (zero, zero, zero), |(pq, pp, qq), (p_i, q_i)| {
+        (pq + p_i * q_i, pp + p_i * p_i, qq + q_i * q_i)
+    });
+    pq / (pp.sqrt() * qq.sqrt())
+}
+
+// Like `check_pair`, but NaN marks a missing value rather than an error. A pair
+// without any coordinate observed in both is `Error::NothingObserved`.
+fn check_missing_pair<T: Element>(p: &[T], q: &[T]) -> Result<()> {
+    if p.len() != q.len() {
+        return Err(Error::LengthMismatch {
+            p: p.len(),
+            q: q.len(),
+        });
+    }
+    if p.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    if let Some(index) = p
+        .iter()
+        .zip(q.iter())
+        .position(|(&p_i, &q_i)| p_i.to_float().is_infinite() || q_i.to_float().is_infinite())
+    {
+        return Err(Error::NonFinite { index });
+    }
+    let (_, observed) = fold_observed(p, q, (), |_, _| ());
+    if observed == 0 {
+        return Err(Error::NothingObserved);
+    }
+    Ok(())
+}
+
+// Without a division, only an overflow makes the result non-finite
+fallible! {
+    check_missing_pair, check_overflow;
+    try_euclidean => euclidean,
+    try_manhattan => manhattan,
+    try_chebyshev => chebyshev,
+}
+
+fallible! {
+    check_missing_pair;
+    try_canberra => canberra,
+    try_gower => gower,
+    try_cosine => cosine,
+}
+
+pub fn try_minkowski<T: Element>(p: &[T], q: &[T], d: f64) -> Result<T::Float> {
+    check_missing_pair(p, q)?;
+    // Only a positive order keeps the powers away from a zero base
+    if d > 0.0 {
+        check_overflow(minkowski(p, q, d))
+    } else {
+        check_result(minkowski(p, q, d))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{distance, nan, similarity, Error};
+
+    const P: [f64; 3] = [0.000, 1.700, 2.350];
+    const Q: [f64; 3] = [0.300, 1.700, 1.001];
+    const P_GAP: [f64; 3] = [0.000, f64::NAN, 2.350];
+    const Q_GAP: [f64; 3] = [0.300, 1.700, f64::NAN];
+
+    #[test]
+    fn complete_inputs() {
+        assert_eq!(nan::euclidean(&P, &Q), distance::euclidean(&P, &Q));
+        assert_eq!(nan::manhattan(&P, &Q), distance::manhattan(&P, &Q));
+        assert_relative_eq!(
+            nan::minkowski(&P, &Q, 3.0),
+            distance::minkowski(&P, &Q, 3.0),
+            epsilon = 1e-12
+        );
+        assert_eq!(nan::chebyshev(&P, &Q), distance::chebyshev(&P, &Q));
+        assert_relative_eq!(nan::gower(&P, &Q), distance::gower(&P, &Q), epsilon = 1e-12);
+        assert_relative_eq!(
+            nan::cosine(&P, &Q),
+            similarity::cosine(&P, &Q),
+            epsilon = 1e-12
+        );
+        let (p, q) = ([1.0, 2.0, 3.0], [2.0, 2.0, 1.0]);
+        assert_relative_eq!(
+            nan::canberra(&p, &q),
+            distance::canberra(&p, &q),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn euclidean() {
+        // scikit-learn: sqrt(3 / 2 * ((0 - 0.3)^2 + (2.35 - 1.001)^2))
+        let result = nan::euclidean(&P_GAP, &Q);
+        assert_relative_eq!(result, 1.692_542_909, epsilon = 1e-9);
+        // Only the first coordinate is observed in both
+        let result = nan::euclidean(&P_GAP, &Q_GAP);
+        assert_relative_eq!(result, 0.3 * 3.0_f64.sqrt(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn manhattan() {
+        let result = nan::manhattan(&P_GAP, &Q);
+        assert_relative_eq!(result, 1.5 * 1.649, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn minkowski() {
+        let result = nan::minkowski(&P_GAP, &Q, 3.0);
+        assert_relative_eq!(result, 1.549_860_178, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn chebyshev() {
+        assert_relative_eq!(nan::chebyshev(&P_GAP, &Q), 1.349, epsilon = 1e-12);
+        assert_relative_eq!(nan::chebyshev(&P_GAP, &Q_GAP), 0.3, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn canberra() {
+        let result = nan::canberra(&P_GAP, &Q);
+        assert_relative_eq!(result, 1.5 * (1.0 + 1.349 / 3.351), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn gower() {
+        let result = nan::gower(&P_GAP, &Q);
+        assert_relative_eq!(result, 1.649 / 2.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn cosine() {
+        let result = nan::cosine(&P_GAP, &Q);
+        assert_relative_eq!(result, similarity::cosine(&[0.0, 2.35], &[0.3, 1.001]));
+    }
+
+    #[test]
+    fn nothing_observed() {
+        let p = [f64::NAN, 1.0];
+        let q = [1.0, f64::NAN];
+        assert!(nan::euclidean(&p, &q).is_nan());
+        assert!(nan::chebyshev(&p, &q).is_nan());
+        assert!(nan::gower(&p, &q).is_nan());
+        assert_eq!(nan::try_manhattan(&p, &q), Err(Error::NothingObserved));
+        assert_eq!(nan::try_cosine(&p, &q), Err(Error::NothingObserved));
+    }
+
+    #[test]
+    fn try_overflow() {
+        let (p, q) = ([1e200, f64::NAN], [-1e200, 0.0]);
+        assert_eq!(nan::try_euclidean(&p, &q), Err(Error::Overflow));
+        assert_eq!(nan::try_minkowski(&p, &q, 3.0), Err(Error::Overflow));
+        assert_eq!(
+            nan::try_cosine(&[0.0, 1.0], &[1.0, f64::NAN]),
+            Err(Error::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn try_missing() {
+        assert_eq!(
+            nan::try_euclidean(&P_GAP, &Q),
+            Ok(nan::euclidean(&P_GAP, &Q))
+        );
+        assert_eq!(
+            nan::try_minkowski(&P_GAP, &Q, 3.0),
+            Ok(nan::minkowski(&P_GAP, &Q, 3.0))
+        );
+        assert_eq!(
+            nan::try_cosine(&P_GAP, &[1.0, f64::INFINITY, 0.0]),
+            Err(Error::NonFinite { index: 1 })
+        );
+        assert_eq!(
+            nan::try_gower(&P_GAP, &Q[..2]),
+            Err(Error::LengthMismatch { p: 3, q: 2 })
+        );
+        assert_eq!(nan::try_gower::<f64>(&[], &[]), Err(Error::EmptyInput));
+    }
+
+    #[test]
+    fn f32_missing() {
+        let p = P_GAP.map(|v| v as f32);
+        let q = Q.map(|v| v as f32);
+        assert_relative_eq!(nan::euclidean(&p, &q), 1.692_542_9, epsilon = 1e-6);
+    }
+}
@@ -0,0 +1,248 @@
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+// Datasets are flat row-major buffers: row `i` is `data[i * dim..(i + 1) * dim]`.
+
+pub(crate) fn check_dim<T>(data: &[T], dim: usize) {
+    assert!(dim > 0, "dimension must be non-zero");
+    assert_eq!(
+        data.len() % dim,
+        0,
+        "buffer length is not a multiple of dim"
+    );
+}
+
+pub(crate) fn rows<T>(data: &[T], dim: usize) -> std::slice::ChunksExact<'_, T> {
+    check_dim(data, dim);
+    data.chunks_exact(dim)
+}
+
+// Measure between every pair of rows `i < j`, in the condensed (upper triangle,
+// row by row) layout used by scipy's `pdist`.
+pub fn pdist<T, R, F>(data: &[T], dim: usize, measure: F) -> Vec<R>
+where
+    F: Fn(&[T], &[T]) -> R,
+{
+    let n = rows(data, dim).len();
+    let mut result = Vec::with_capacity(n * n.saturating_sub(1) / 2);
+
+    for (i, p) in rows(data, dim).enumerate() {
+        result.extend(rows(data, dim).skip(i + 1).map(|q| measure(p, q)));
+    }
+
+    result
+}
+
+// Measure between every row of `a` and every row of `b`, as a row-major
+// `a_rows x b_rows` matrix.
+pub fn cdist<T, R, F>(a: &[T], b: &[T], dim: usize, measure: F) -> Vec<R>
+where
+    F: Fn(&[T], &[T]) -> R,
+{
+    let mut result = Vec::with_capacity(rows(a, dim).len() * rows(b, dim).len());
+
+    for p in rows(a, dim) {
+        result.extend(rows(b, dim).map(|q| measure(p, q)));
+    }
+
+    result
+}
+
+// Measure between `query` and every row of `data`
+pub fn query<T, R, F>(query: &[T], data: &[T], dim: usize, measure: F) -> Vec<R>
+where
+    F: Fn(&[T], &[T]) -> R,
+{
+    assert_eq!(query.len(), dim, "query length does not match dim");
+    rows(data, dim).map(|row| measure(query, row)).collect()
+}
+
+// The `par_*` versions compute exactly the same values as their serial
+// counterparts, in the same order.
+#[cfg(feature = "rayon")]
+pub fn par_pdist<T, R, F>(data: &[T], dim: usize, measure: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&[T], &[T]) -> R + Sync,
+{
+    let n = rows(data, dim).len();
+
+    (0..n)
+        .into_par_iter()
+        .flat_map_iter(|i| {
+            let p = &data[i * dim..(i + 1) * dim];
+            let measure = &measure;
+            rows(data, dim).skip(i + 1).map(move |q| measure(p, q))
+        })
+        .collect()
+}
+
+#[cfg(feature = "rayon")]
+pub fn par_cdist<T, R, F>(a: &[T], b: &[T], dim: usize, measure: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&[T], &[T]) -> R + Sync,
+{
+    check_dim(a, dim);
+    check_dim(b, dim);
+
+    a.par_chunks_exact(dim)
+        .flat_map_iter(|p| {
+            let measure = &measure;
+            rows(b, dim).map(move |q| measure(p, q))
+        })
+        .collect()
+}
+
+#[cfg(feature = "rayon")]
+pub fn par_query<T, R, F>(query: &[T], data: &[T], dim: usize, measure: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&[T], &[T]) -> R + Sync,
+{
+    assert_eq!(query.len(), dim, "query length does not match dim");
+    check_dim(data, dim);
+    data.par_chunks_exact(dim)
+        .map(|row| measure(query, row))
+        .collect()
+}
+
+// Number of rows `n` such that `n * (n - 1) / 2 == len`. An empty matrix
+// could come from one row as well as none, and is taken as none.
+pub fn condensed_rows(len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    let n = ((1.0 + (1.0 + 8.0 * len as f64).sqrt()) / 2.0).round() as usize;
+    assert_eq!(n * (n - 1) / 2, len, "invalid condensed matrix length");
+    n
+}
+
+// Position of the pair `(i, j)` in a condensed matrix over `n` rows
+pub fn condensed_index(n: usize, i: usize, j: usize) -> usize {
+    assert!(
+        i != j && i < n && j < n,
+        "invalid pair ({i}, {j}) for {n} rows"
+    );
+    let (i, j) = if i < j { (i, j) } else { (j, i) };
+    n * i - i * (i + 1) / 2 + j - i - 1
+}
+
+// Expands a condensed matrix to a symmetric `n x n` one. The diagonal is not
+// stored, so it has to be given: `0.0` for distances, self-similarity otherwise.
+pub fn to_square<R: Copy>(condensed: &[R], diagonal: R) -> Vec<R> {
+    let n = condensed_rows(condensed.len());
+    let mut square = vec![diagonal; n * n];
+    let mut values = condensed.iter();
+
+    for i in 0..n {
+        for j in i + 1..n {
+            let value = *values.next().unwrap();
+            square[i * n + j] = value;
+            square[j * n + i] = value;
+        }
+    }
+
+    square
+}
+
+// Keeps the strict upper triangle of a square `n x n` matrix
+pub fn to_condensed<R: Copy>(square: &[R], n: usize) -> Vec<R> {
+    assert_eq!(square.len(), n * n, "matrix is not n x n");
+    (0..n)
+        .flat_map(|i| square[i * n + i + 1..(i + 1) * n].iter().copied())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{distance, pairwise, similarity};
+
+    const DATA: [f64; 9] = [
+        0.000, 1.700, 2.350, 0.300, 1.700, 1.001, 1.000, 0.000, 1.000,
+    ];
+
+    #[test]
+    fn pdist() {
+        let result = pairwise::pdist(&DATA, 3, distance::euclidean);
+        assert_eq!(result.len(), 3);
+        assert_relative_eq!(result[0], 1.381955499, epsilon = 1e-9);
+        assert_eq!(result[1], distance::euclidean(&DATA[0..3], &DATA[6..9]));
+        assert_eq!(result[2], distance::euclidean(&DATA[3..6], &DATA[6..9]));
+    }
+
+    #[test]
+    fn cdist() {
+        let result = pairwise::cdist(&DATA[..6], &DATA, 3, similarity::cosine);
+        assert_eq!(result.len(), 6);
+        assert_relative_eq!(result[1], 0.905759279, epsilon = 1e-9);
+        assert_relative_eq!(result[3], 0.905759279, epsilon = 1e-9);
+        assert_eq!(result[5], similarity::cosine(&DATA[3..6], &DATA[6..9]));
+    }
+
+    #[test]
+    fn pdist_f32() {
+        let data = DATA.map(|v| v as f32);
+        let result: Vec<f32> = pairwise::pdist(&data, 3, distance::euclidean);
+        assert_relative_eq!(result[0], 1.381_955_5, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn square_round_trip() {
+        let condensed = pairwise::pdist(&DATA, 3, distance::manhattan);
+        let square = pairwise::to_square(&condensed, 0.0);
+
+        assert_eq!(square.len(), 9);
+        assert_eq!(square[1], condensed[0]);
+        assert_eq!(square[7], condensed[2]);
+        assert_eq!(square[4], 0.0);
+        assert_eq!(pairwise::to_condensed(&square, 3), condensed);
+    }
+
+    #[test]
+    fn condensed_index() {
+        assert_eq!(pairwise::condensed_rows(10), 5);
+        assert_eq!(pairwise::condensed_rows(1), 2);
+        assert_eq!(pairwise::condensed_rows(0), 0);
+        assert_eq!(pairwise::condensed_index(5, 0, 1), 0);
+        assert_eq!(pairwise::condensed_index(5, 1, 0), 0);
+        assert_eq!(pairwise::condensed_index(5, 1, 2), 4);
+        assert_eq!(pairwise::condensed_index(5, 3, 4), 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid condensed matrix length")]
+    fn condensed_rows_not_triangular() {
+        pairwise::condensed_rows(4);
+    }
+
+    #[test]
+    fn query() {
+        let result = pairwise::query(&DATA[..3], &DATA, 3, distance::euclidean);
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0], 0.0);
+        assert_relative_eq!(result[1], 1.381955499, epsilon = 1e-9);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_matches_serial() {
+        let data: Vec<f64> = (0..64 * 5).map(|i| ((i * 37) % 101) as f64 / 7.0).collect();
+
+        assert_eq!(
+            pairwise::par_pdist(&data, 5, distance::hellinger),
+            pairwise::pdist(&data, 5, distance::hellinger)
+        );
+        assert_eq!(
+            pairwise::par_cdist(&data[..40], &data, 5, similarity::cosine),
+            pairwise::cdist(&data[..40], &data, 5, similarity::cosine)
+        );
+        assert_eq!(
+            pairwise::par_query(&data[..5], &data, 5, distance::manhattan),
+            pairwise::query(&data[..5], &data, 5, distance::manhattan)
+        );
+    }
+}
@@ -0,0 +1,483 @@
+// Product quantization. Vectors are cut into `subspaces` equal slices and every
+// slice is replaced by the byte index of its nearest centroid in a k-means
+// codebook trained for that slice. Distances between a raw query and encoded
+// vectors are computed asymmetrically from per-query lookup tables.
+
+use crate::knn::{Nearest, Neighbour};
+use crate::pairwise::rows;
+use crate::rng::Rng;
+use crate::simd;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Params {
+    pub subspaces: usize,
+    // Codebook size per subspace, at most 256 so that codes fit in a byte
+    pub centroids: usize,
+    pub iterations: usize,
+    pub seed: u64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            subspaces: 8,
+            centroids: 256,
+            iterations: 25,
+            seed: 0x5eed,
+        }
+    }
+}
+
+fn nearest(centroids: &[f32], dim: usize, vector: &[f32]) -> usize {
+    centroids
+        .chunks_exact(dim)
+        .map(|c| simd::squared_euclidean(vector, c))
+        .enumerate()
+        .fold(
+            (0, f32::INFINITY),
+            |best, (i, d)| if d < best.1 { (i, d) } else { best },
+        )
+        .0
+}
+
+// Lloyd's k-means with k-means++ seeding, returning `k` centroids. Clusters
+// that end up empty keep their previous centroid.
+fn kmeans(data: &[f32], dim: usize, k: usize, iterations: usize, rng: &mut Rng) -> Vec<f32> {
+    let n = rows(data, dim).len();
+    assert!(n > 0, "k-means needs at least one vector");
+    let row = |i: usize| &data[i * dim..(i + 1) * dim];
+
+    let mut centroids = row(rng.below(n)).to_vec();
+    let mut weights: Vec<f64> = (0..n)
+        .map(|i| simd::squared_euclidean(row(i), &centroids[..dim]) as f64)
+        .collect();
+    for _ in 1..k {
+        let total: f64 = weights.iter().sum();
+        let chosen = if total > 0.0 {
+            let mut target = rng.uniform() * total;
+            weights
+                .iter()
+                .position(|&w| {
+                    target -= w;
+                    target <= 0.0
+                })
+                .unwrap_or(n - 1)
+        } else {
+            rng.below(n)
+        };
+        centroids.extend_from_slice(row(chosen));
+        let added = &centroids[centroids.len() - dim..];
+        for (i, weight) in weights.iter_mut().enumerate() {
+            *weight = weight.min(simd::squared_euclidean(row(i), added) as f64);
+        }
+    }
+
+    for _ in 0..iterations {
+        let mut sums = vec![0.0f64; k * dim];
+        let mut counts = vec![0usize; k];
+        for i in 0..n {
+            let cluster = nearest(&centroids, dim, row(i));
+            counts[cluster] += 1;
+            for (sum, &v) in sums[cluster * dim..].iter_mut().zip(row(i)) {
+                *sum += v as f64;
+            }
+        }
+        for (cluster, &count) in counts.iter().enumerate().filter(|(_, &c)| c > 0) {
+            for j in cluster * dim..(cluster + 1) * dim {
+                centroids[j] = (sums[j] / count as f64) as f32;
+            }
+        }
+    }
+
+    centroids
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductQuantizer {
+    dim: usize,
+    subspaces: usize,
+    centroids: usize,
+    // `codebooks[s][c]` is centroid `c` of subspace `s`, flattened
+    codebooks: Vec<f32>,
+}
+
+impl ProductQuantizer {
+    pub fn train(data: &[f32], dim: usize, params: Params) -> Self {
+        assert!(
+            params.subspaces > 0 && dim.is_multiple_of(params.subspaces),
+            "dim must be a multiple of the number of subspaces"
+        );
+        assert!(
+            (1..=256).contains(&params.centroids),
+            "codebooks hold between 1 and 256 centroids"
+        );
+        let sub_dim = dim / params.subspaces;
+        let mut rng = Rng::new(params.seed);
+
+        let mut codebooks = Vec::with_capacity(params.subspaces * params.centroids * sub_dim);
+        for s in 0..params.subspaces {
+            let slices: Vec<f32> = rows(data, dim)
+                .flat_map(|row| &row[s * sub_dim..(s + 1) * sub_dim])
+                .copied()
+                .collect();
+            codebooks.extend(kmeans(
+                &slices,
+                sub_dim,
+                params.centroids,
+                params.iterations,
+                &mut rng,
+            ));
+        }
+
+        ProductQuantizer {
+            dim,
+            subspaces: params.subspaces,
+            centroids: params.centroids,
+            codebooks,
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    // Bytes per encoded vector
+    pub fn code_len(&self) -> usize {
+        self.subspaces
+    }
+
+    fn sub_dim(&self) -> usize {
+        self.dim / self.subspaces
+    }
+
+    fn codebook(&self, subspace: usize) -> &[f32] {
+        let size = self.centroids * self.sub_dim();
+        &self.codebooks[subspace * size..(subspace + 1) * size]
+    }
+
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        assert_eq!(vector.len(), self.dim, "vector length does not match dim");
+        vector
+            .chunks_exact(self.sub_dim())
+            .enumerate()
+            .map(|(s, slice)| nearest(self.codebook(s), self.sub_dim(), slice) as u8)
+            .collect()
+    }
+
+    // Codes of every row of `data`, concatenated
+    pub fn encode_all(&self, data: &[f32]) -> Vec<u8> {
+        rows(data, self.dim)
+            .flat_map(|row| self.encode(row))
+            .collect()
+    }
+
+    pub fn decode(&self, code: &[u8]) -> Vec<f32> {
+        assert_eq!(code.len(), self.subspaces, "code length does not match");
+        let sub_dim = self.sub_dim();
+        code.iter()
+            .enumerate()
+            .flat_map(|(s, &c)| &self.codebook(s)[c as usize * sub_dim..][..sub_dim])
+            .copied()
+            .collect()
+    }
+
+    fn table(&self, query: &[f32], measure: fn(&[f32], &[f32]) -> f32) -> LookupTable {
+        assert_eq!(query.len(), self.dim, "query length does not match dim");
+        let sub_dim = self.sub_dim();
+        let values = query
+            .chunks_exact(sub_dim)
+            .enumerate()
+            .flat_map(|(s, slice)| {
+                self.codebook(s)
+                    .chunks_exact(sub_dim)
+                    .map(move |c| measure(slice, c))
+            })
+            .collect();
+        LookupTable {
+            centroids: self.centroids,
+            values,
+        }
+    }
+
+    // Table approximating `distance::squared_euclidean(query, x)` from codes
+    pub fn squared_euclidean_table(&self, query: &[f32]) -> LookupTable {
+        self.table(query, simd::squared_euclidean)
+    }
+
+    // Table approximating `distance::inner_product(query, x)` from codes
+    pub fn inner_product_table(&self, query: &[f32]) -> LookupTable {
+        self.table(query, simd::inner_product)
+    }
+}
+
+// Per-query partial results for every centroid of every subspace. Both
+// squared euclidean and inner product decompose into a sum over subspaces,
+// so a code is evaluated with one lookup per byte.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LookupTable {
+    centroids: usize,
+    values: Vec<f32>,
+}
+
+impl LookupTable {
+    pub fn evaluate(&self, code: &[u8]) -> f32 {
+        code.iter()
+            .enumerate()
+            .map(|(s, &c)| self.values[s * self.centroids + c as usize])
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Space {
+    SquaredEuclidean,
+    InnerProduct,
+}
+
+#[derive(Debug, Clone, Default)]
+struct List {
+    ids: Vec<usize>,
+    codes: Vec<u8>,
+}
+
+// Inverted file over a coarse k-means quantizer, with the residual of every
+// vector to its coarse centroid product-quantized. A search only scans the
+// `probes` lists whose centroids are closest to the query.
+#[derive(Debug, Clone)]
+pub struct IvfPq {
+    space: Space,
+    dim: usize,
+    coarse: Vec<f32>,
+    pq: ProductQuantizer,
+    lists: Vec<List>,
+    len: usize,
+}
+
+impl IvfPq {
+    // Trains the coarse quantizer with `lists` centroids and the product
+    // quantizer on the residuals of `data`. No vector is added yet.
+    pub fn train(data: &[f32], dim: usize, lists: usize, space: Space, params: Params) -> Self {
+        assert!(lists > 0, "at least one list is required");
+        let mut rng = Rng::new(params.seed ^ 0x1f);
+        let coarse = kmeans(data, dim, lists, params.iterations, &mut rng);
+        let residuals: Vec<f32> = rows(data, dim)
+            .flat_map(|row| residual(&coarse, dim, row).1)
+            .collect();
+
+        IvfPq {
+            space,
+            dim,
+            pq: ProductQuantizer::train(&residuals, dim, params),
+            lists: vec![List::default(); lists],
+            coarse,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Adds a vector and returns its index
+    pub fn add(&mut self, vector: &[f32]) -> usize {
+        assert_eq!(vector.len(), self.dim, "vector length does not match dim");
+        let (list, residual) = residual(&self.coarse, self.dim, vector);
+        let index = self.len;
+        self.lists[list].ids.push(index);
+        self.lists[list].codes.extend(self.pq.encode(&residual));
+        self.len += 1;
+        index
+    }
+
+    pub fn add_all(&mut self, data: &[f32]) {
+        for row in rows(data, self.dim) {
+            self.add(row);
+        }
+    }
+
+    // The `k` best vectors among the `probes` closest lists: smallest squared
+    // euclidean distance or largest inner product first
+    pub fn search(&self, query: &[f32], k: usize, probes: usize) -> Vec<Neighbour<f32>> {
+        assert_eq!(query.len(), self.dim, "query length does not match dim");
+        let mut lists: Vec<(f32, usize)> = self
+            .coarse
+            .chunks_exact(self.dim)
+            .map(|c| simd::squared_euclidean(query, c))
+            .zip(0..)
+            .collect();
+        lists.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        let mut nearest = Nearest::new(k);
+        for &(_, list) in lists.iter().take(probes) {
+            let centroid = &self.coarse[list * self.dim..(list + 1) * self.dim];
+            // <q, c + r> = <q, c> + <q, r> and |q - c - r|^2 = |(q - c) - r|^2
+            let (table, offset) = match self.space {
+                Space::SquaredEuclidean => {
+                    let shifted: Vec<f32> =
+                        query.iter().zip(centroid).map(|(q, c)| q - c).collect();
+                    (self.pq.squared_euclidean_table(&shifted), 0.0)
+                }
+                Space::InnerProduct => (
+                    self.pq.inner_product_table(query),
+                    simd::inner_product(query, centroid),
+                ),
+            };
+
+            let List { ids, codes } = &self.lists[list];
+            for (&id, code) in ids.iter().zip(codes.chunks_exact(self.pq.code_len())) {
+                let score = offset + table.evaluate(code);
+                nearest.push(
+                    id,
+                    match self.space {
+                        Space::SquaredEuclidean => score,
+                        Space::InnerProduct => -score,
+                    },
+                );
+            }
+        }
+
+        let mut neighbours = nearest.into_neighbours();
+        if self.space == Space::InnerProduct {
+            for neighbour in &mut neighbours {
+                neighbour.score = -neighbour.score;
+            }
+        }
+        neighbours
+    }
+}
+
+// The nearest coarse centroid and the residual of `vector` to it
+fn residual(coarse: &[f32], dim: usize, vector: &[f32]) -> (usize, Vec<f32>) {
+    let list = nearest(coarse, dim, vector);
+    let centroid = &coarse[list * dim..(list + 1) * dim];
+    (
+        list,
+        vector.iter().zip(centroid).map(|(v, c)| v - c).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pq::{IvfPq, Params, ProductQuantizer, Space};
+    use crate::rng::samples;
+    use crate::{distance, knn};
+
+    // Gaussian blobs around a few random centres
+    fn clustered(n: usize, dim: usize, seed: u64) -> Vec<f32> {
+        let centres = samples(8 * dim, seed, |rng| 4.0 * rng.gaussian());
+        // Another stream, so that the rows of one seed do not pick up the
+        // centres of the next
+        samples(n, !seed, |rng| {
+            let centre = rng.below(8) * dim;
+            (0..dim)
+                .map(|j| (centres[centre + j] + rng.gaussian()) as f32)
+                .collect::<Vec<_>>()
+        })
+        .concat()
+    }
+
+    fn params(subspaces: usize, centroids: usize) -> Params {
+        Params {
+            subspaces,
+            centroids,
+            iterations: 10,
+            ..Params::default()
+        }
+    }
+
+    // Mean squared reconstruction error relative to the mean squared norm
+    fn relative_error(pq: &ProductQuantizer, data: &[f32]) -> f32 {
+        let (error, norm) = data
+            .chunks(pq.dim())
+            .map(|row| {
+                let restored = pq.decode(&pq.encode(row));
+                (
+                    distance::squared_euclidean(row, &restored),
+                    distance::inner_product(row, row),
+                )
+            })
+            .fold((0.0, 0.0), |(e, n), (row_e, row_n)| (e + row_e, n + row_n));
+        error / norm
+    }
+
+    #[test]
+    fn reconstruction_error() {
+        let data = clustered(1000, 16, 1);
+        let coarse = ProductQuantizer::train(&data, 16, params(4, 16));
+        let fine = ProductQuantizer::train(&data, 16, params(8, 64));
+
+        assert_eq!(fine.encode(&data[..16]).len(), 8);
+        assert_eq!(fine.encode_all(&data).len(), 8000);
+        let (coarse_error, fine_error) =
+            (relative_error(&coarse, &data), relative_error(&fine, &data));
+        assert!(fine_error < coarse_error);
+        assert!(fine_error < 0.05, "relative error {fine_error}");
+    }
+
+    #[test]
+    fn tables_match_decoded() {
+        let data = clustered(500, 12, 2);
+        let pq = ProductQuantizer::train(&data, 12, params(3, 32));
+        let query = &data[12..24];
+        let euclidean = pq.squared_euclidean_table(query);
+        let inner = pq.inner_product_table(query);
+
+        for row in data.chunks(12).take(50) {
+            let code = pq.encode(row);
+            let restored = pq.decode(&code);
+            assert_relative_eq!(
+                euclidean.evaluate(&code),
+                distance::squared_euclidean(query, &restored),
+                max_relative = 1e-4
+            );
+            assert_relative_eq!(
+                inner.evaluate(&code),
+                distance::inner_product(query, &restored),
+                max_relative = 1e-3,
+                epsilon = 1e-3
+            );
+        }
+    }
+
+    #[test]
+    fn ivf_pq_recall() {
+        let (dim, k) = (16, 10);
+        let data = clustered(1000, dim, 3);
+        let queries = clustered(40, dim, 4);
+
+        for space in [Space::SquaredEuclidean, Space::InnerProduct] {
+            let mut index = IvfPq::train(&data, dim, 16, space, params(8, 64));
+            index.add_all(&data);
+            assert_eq!(index.len(), 1000);
+
+            let mut hits = 0;
+            for query in queries.chunks(dim) {
+                let exact = match space {
+                    Space::SquaredEuclidean => {
+                        knn::knn(query, &data, dim, 1, distance::squared_euclidean)
+                    }
+                    Space::InnerProduct => {
+                        knn::knn_similarity(query, &data, dim, 1, distance::inner_product)
+                    }
+                };
+                let found = index.search(query, k, 6);
+                assert_eq!(found.len(), k);
+                hits += usize::from(found.iter().any(|n| n.index == exact[0].index));
+            }
+            assert!(hits >= 36, "{space:?} recall@{k} {hits}/40");
+        }
+    }
+
+    #[test]
+    fn deterministic_under_seed() {
+        let data = clustered(300, 8, 5);
+        assert_eq!(
+            ProductQuantizer::train(&data, 8, params(2, 16)),
+            ProductQuantizer::train(&data, 8, params(2, 16))
+        );
+    }
+}
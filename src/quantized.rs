@@ -0,0 +1,236 @@
+// Int8 quantized vectors: each stored value `v` stands for `scale * (v - zero_point)`.
+// The kernels accumulate in integers and only apply the scales at the end.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantized {
+    pub values: Vec<i8>,
+    pub scale: f32,
+    pub zero_point: i8,
+}
+
+impl Quantized {
+    pub fn new(values: Vec<i8>, scale: f32, zero_point: i8) -> Self {
+        Quantized {
+            values,
+            scale,
+            zero_point,
+        }
+    }
+
+    // Asymmetric min/max quantization over the full i8 range
+    pub fn quantize(data: &[f32]) -> Self {
+        let min = data.iter().copied().fold(0.0, f32::min);
+        let max = data.iter().copied().fold(0.0, f32::max);
+        let scale = if max > min { (max - min) / 255.0 } else { 1.0 };
+        let zero_point = (-128.0 - min / scale).round().clamp(-128.0, 127.0) as i8;
+
+        let values = data
+            .iter()
+            .map(|&v| (v / scale + zero_point as f32).round().clamp(-128.0, 127.0) as i8)
+            .collect();
+
+        Quantized::new(values, scale, zero_point)
+    }
+
+    pub fn dequantize(&self) -> Vec<f32> {
+        self.values
+            .iter()
+            .map(|&v| self.scale * (v as i32 - self.zero_point as i32) as f32)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+// i8 products are at most 2^14 in magnitude, so 2^16 of them cannot overflow an
+// i32 accumulator
+const BLOCK: usize = 1 << 16;
+
+// Squared i8 differences reach 255^2, so only 2^15 of them fit an i32
+const DIFF_BLOCK: usize = 1 << 15;
+
+#[derive(Default)]
+struct Sums {
+    pq: i64,
+    p: i64,
+    q: i64,
+    pp: i64,
+    qq: i64,
+}
+
+fn sums(p: &[i8], q: &[i8]) -> Sums {
+    let mut total = Sums::default();
+
+    for (p, q) in p.chunks(BLOCK).zip(q.chunks(BLOCK)) {
+        let (mut pq, mut p_sum, mut q_sum, mut pp, mut qq) = (0i32, 0i32, 0i32, 0i32, 0i32);
+        for (&p_i, &q_i) in p.iter().zip(q) {
+            let (p_i, q_i) = (p_i as i32, q_i as i32);
+            pq += p_i * q_i;
+            p_sum += p_i;
+            q_sum += q_i;
+            pp += p_i * p_i;
+            qq += q_i * q_i;
+        }
+        total.pq += pq as i64;
+        total.p += p_sum as i64;
+        total.q += q_sum as i64;
+        total.pp += pp as i64;
+        total.qq += qq as i64;
+    }
+
+    total
+}
+
+// Integer sums of `(p_i - zp_p) * (q_i - zp_q)`, `(p_i - zp_p)^2` and `(q_i - zp_q)^2`
+fn centered(p: &Quantized, q: &Quantized) -> (i64, i64, i64) {
+    let n = p.len().min(q.len()) as i64;
+    let s = sums(&p.values, &q.values);
+    let (zp, zq) = (p.zero_point as i64, q.zero_point as i64);
+
+    (
+        s.pq - zq * s.p - zp * s.q + n * zp * zq,
+        s.pp - 2 * zp * s.p + n * zp * zp,
+        s.qq - 2 * zq * s.q + n * zq * zq,
+    )
+}
+
+pub fn inner_product(p: &Quantized, q: &Quantized) -> f32 {
+    let (pq, _, _) = centered(p, q);
+    (p.scale as f64 * q.scale as f64 * pq as f64) as f32
+}
+
+pub fn squared_euclidean(p: &Quantized, q: &Quantized) -> f32 {
+    // Same quantization parameters: the difference is exact in integers
+    if p.scale == q.scale && p.zero_point == q.zero_point {
+        let mut sum = 0i64;
+        for (p, q) in p.values.chunks(DIFF_BLOCK).zip(q.values.chunks(DIFF_BLOCK)) {
+            sum += p
+                .iter()
+                .zip(q)
+                .map(|(&p_i, &q_i)| (p_i as i32 - q_i as i32).pow(2))
+                .sum::<i32>() as i64;
+        }
+        return (p.scale as f64 * p.scale as f64 * sum as f64) as f32;
+    }
+
+    let (pq, pp, qq) = centered(p, q);
+    let (sp, sq) = (p.scale as f64, q.scale as f64);
+    (sp * sp * pp as f64 + sq * sq * qq as f64 - 2.0 * sp * sq * pq as f64).max(0.0) as f32
+}
+
+pub fn cosine(p: &Quantized, q: &Quantized) -> f32 {
+    // The scales are positive and cancel out
+    let (pq, pp, qq) = centered(p, q);
+    (pq as f64 / ((pp as f64).sqrt() * (qq as f64).sqrt())) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::quantized::{self, Quantized};
+    use crate::{distance, similarity};
+
+    fn vectors(n: usize) -> (Vec<f32>, Vec<f32>) {
+        let p = (0..n).map(|i| ((i * 31) % 97) as f32 / 9.7 - 3.0).collect();
+        let q = (0..n)
+            .map(|i| ((i * 17) % 89) as f32 / 17.8 - 1.0)
+            .collect();
+        (p, q)
+    }
+
+    #[test]
+    fn quantize_round_trip() {
+        let (p, _) = vectors(768);
+        let quantized = Quantized::quantize(&p);
+        let restored = quantized.dequantize();
+
+        for (original, restored) in p.iter().zip(&restored) {
+            assert!((original - restored).abs() <= quantized.scale / 2.0 + 1e-6);
+        }
+    }
+
+    #[test]
+    fn matches_dequantized() {
+        let (p, q) = vectors(768);
+        let (p, q) = (Quantized::quantize(&p), Quantized::quantize(&q));
+        let (p_f, q_f) = (p.dequantize(), q.dequantize());
+
+        assert_relative_eq!(
+            quantized::inner_product(&p, &q),
+            distance::inner_product(&p_f, &q_f),
+            max_relative = 1e-4
+        );
+        assert_relative_eq!(
+            quantized::squared_euclidean(&p, &q),
+            distance::squared_euclidean(&p_f, &q_f),
+            max_relative = 1e-4
+        );
+        assert_relative_eq!(quantized::squared_euclidean(&p, &p), 0.0, epsilon = 1e-6);
+        assert_relative_eq!(
+            quantized::cosine(&p, &q),
+            similarity::cosine(&p_f, &q_f),
+            max_relative = 1e-4
+        );
+    }
+
+    #[test]
+    fn shared_parameters() {
+        let p = Quantized::new(vec![-128, 0, 5, 127], 0.5, -3);
+        let q = Quantized::new(vec![127, 1, -5, 100], 0.5, -3);
+
+        assert_relative_eq!(
+            quantized::squared_euclidean(&p, &q),
+            distance::squared_euclidean(&p.dequantize(), &q.dequantize()),
+            max_relative = 1e-6
+        );
+    }
+
+    #[test]
+    fn extreme_values_do_not_overflow() {
+        // Every pair differs by 255, more than an i32 can sum over one block
+        let n = 70_000;
+        let p: Vec<i8> = (0..n)
+            .map(|i| if i % 2 == 0 { -128 } else { 127 })
+            .collect();
+        let q: Vec<i8> = p
+            .iter()
+            .map(|&v| if v == -128 { 127 } else { -128 })
+            .collect();
+        let (p, q) = (Quantized::new(p, 1.0, 0), Quantized::new(q, 1.0, 0));
+
+        assert_eq!(
+            quantized::squared_euclidean(&p, &q),
+            (n as f64 * 255.0 * 255.0) as f32
+        );
+        assert_eq!(
+            quantized::inner_product(&p, &q),
+            (n as f64 * -128.0 * 127.0) as f32
+        );
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn half_precision() {
+        let (p, q) = vectors(64);
+        let p16: Vec<half::f16> = p.iter().map(|&v| half::f16::from_f32(v)).collect();
+        let q16: Vec<half::f16> = q.iter().map(|&v| half::f16::from_f32(v)).collect();
+        let p_bf: Vec<half::bf16> = p.iter().map(|&v| half::bf16::from_f32(v)).collect();
+        let q_bf: Vec<half::bf16> = q.iter().map(|&v| half::bf16::from_f32(v)).collect();
+
+        assert_relative_eq!(
+            similarity::cosine(&p16, &q16),
+            similarity::cosine(&p, &q),
+            max_relative = 1e-3
+        );
+        assert_relative_eq!(
+            distance::squared_euclidean(&p_bf, &q_bf),
+            distance::squared_euclidean(&p, &q),
+            max_relative = 1e-2
+        );
+    }
+}
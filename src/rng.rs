@@ -0,0 +1,66 @@
+// splitmix64 stream. The index structures only need reproducible sampling
+// under a seed, not statistical strength.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mix(self.0)
+    }
+
+    // Uniform in (0, 1]
+    pub(crate) fn uniform(&mut self) -> f64 {
+        ((self.next_u64() >> 11) as f64 + 1.0) / (1u64 << 53) as f64
+    }
+
+    // Uniform in 0..n
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    // Standard normal, by Box-Muller
+    pub(crate) fn gaussian(&mut self) -> f64 {
+        let (u, v) = (self.uniform(), self.uniform());
+        (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+    }
+
+    // Standard Cauchy, by inversion
+    pub(crate) fn cauchy(&mut self) -> f64 {
+        (std::f64::consts::PI * (self.uniform() - 0.5)).tan()
+    }
+}
+
+// splitmix64 finalizer, also used to hash integers
+pub(crate) fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// `len` values drawn with `sample` from a seeded stream, for test fixtures
+#[cfg(test)]
+pub(crate) fn samples<T>(len: usize, seed: u64, mut sample: impl FnMut(&mut Rng) -> T) -> Vec<T> {
+    let mut rng = Rng::new(seed);
+    (0..len).map(|_| sample(&mut rng)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::{samples, Rng};
+
+    #[test]
+    fn reproducible() {
+        let a = samples(100, 3, |rng| rng.below(10));
+        assert_eq!(a, samples(100, 3, |rng| rng.below(10)));
+        assert_ne!(a, samples(100, 4, |rng| rng.below(10)));
+        assert!(a.iter().all(|&v| v < 10));
+        assert!(samples(100, 3, Rng::uniform)
+            .iter()
+            .all(|&v| v > 0.0 && v <= 1.0));
+    }
+}
@@ -0,0 +1,552 @@
+// Vectorized f32/f64 versions of the hot L-family measures. The instruction set
+// is picked at runtime (AVX-512, AVX2, then SSE2 on x86_64) with a scalar
+// fallback on other targets.
+//
+// Tolerance: `chebyshev` is exact, and skips NaN differences as the scalar
+// `max` does. The other kernels sum in lane order instead
+// of element order, so they may differ from `distance::*` / `similarity::*` by
+// the usual reassociation bound of `n * EPSILON` relative to the sum of the
+// absolute terms. For inputs of a few thousand elements this is a handful of
+// ULP in practice. The f32 kernels also accumulate in f32.
+
+pub trait SimdFloat: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    fn squared_euclidean(p: &[Self], q: &[Self]) -> Self;
+    #[doc(hidden)]
+    fn manhattan(p: &[Self], q: &[Self]) -> Self;
+    #[doc(hidden)]
+    fn chebyshev(p: &[Self], q: &[Self]) -> Self;
+    #[doc(hidden)]
+    fn inner_product(p: &[Self], q: &[Self]) -> Self;
+    #[doc(hidden)]
+    fn dot_products(p: &[Self], q: &[Self]) -> (Self, Self, Self);
+    #[doc(hidden)]
+    fn sqrt(self) -> Self;
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+pub fn euclidean<F: SimdFloat>(p: &[F], q: &[F]) -> F {
+    F::squared_euclidean(p, q).sqrt()
+}
+
+pub fn squared_euclidean<F: SimdFloat>(p: &[F], q: &[F]) -> F {
+    F::squared_euclidean(p, q)
+}
+
+pub fn manhattan<F: SimdFloat>(p: &[F], q: &[F]) -> F {
+    F::manhattan(p, q)
+}
+
+pub fn chebyshev<F: SimdFloat>(p: &[F], q: &[F]) -> F {
+    F::chebyshev(p, q)
+}
+
+pub fn inner_product<F: SimdFloat>(p: &[F], q: &[F]) -> F {
+    F::inner_product(p, q)
+}
+
+pub fn cosine<F: SimdFloat + std::ops::Mul<Output = F> + std::ops::Div<Output = F>>(
+    p: &[F],
+    q: &[F],
+) -> F {
+    let (pq, pp, qq) = F::dot_products(p, q);
+    pq / (pp.sqrt() * qq.sqrt())
+}
+
+macro_rules! scalar_kernels {
+    ($t:ty) => {
+        pub(in crate::simd) fn squared_euclidean(p: &[$t], q: &[$t]) -> $t {
+            p.iter()
+                .zip(q)
+                .map(|(p_i, q_i)| (p_i - q_i) * (p_i - q_i))
+                .sum()
+        }
+
+        pub(in crate::simd) fn manhattan(p: &[$t], q: &[$t]) -> $t {
+            p.iter().zip(q).map(|(p_i, q_i)| (p_i - q_i).abs()).sum()
+        }
+
+        pub(in crate::simd) fn chebyshev(p: &[$t], q: &[$t]) -> $t {
+            p.iter()
+                .zip(q)
+                .map(|(p_i, q_i)| (p_i - q_i).abs())
+                .fold(0.0, <$t>::max)
+        }
+
+        pub(in crate::simd) fn inner_product(p: &[$t], q: &[$t]) -> $t {
+            p.iter().zip(q).map(|(p_i, q_i)| p_i * q_i).sum()
+        }
+
+        pub(in crate::simd) fn dot_products(p: &[$t], q: &[$t]) -> ($t, $t, $t) {
+            p.iter()
+                .zip(q)
+                .fold((0.0, 0.0, 0.0), |(pq, pp, qq), (p_i, q_i)| {
+                    (pq + p_i * q_i, pp + p_i * p_i, qq + q_i * q_i)
+                })
+        }
+    };
+}
+
+mod scalar {
+    pub(in crate::simd) mod f32 {
+        scalar_kernels!(f32);
+    }
+
+    pub(in crate::simd) mod f64 {
+        scalar_kernels!(f64);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+macro_rules! x86_kernels {
+    (
+        $feature:literal, $t:ident, $lanes:literal, $reg:ty,
+        $zero:ident, $load:ident, $store:ident,
+        $add:ident, $sub:ident, $mul:ident, $max:ident, $abs:ident
+    ) => {
+        #[allow(unused_imports)]
+        use std::arch::x86_64::*;
+
+        #[inline]
+        #[target_feature(enable = $feature)]
+        unsafe fn lanes(v: $reg) -> [$t; $lanes] {
+            let mut out = [0.0; $lanes];
+            $store(out.as_mut_ptr(), v);
+            out
+        }
+
+        #[inline]
+        #[target_feature(enable = $feature)]
+        unsafe fn load(s: &[$t], chunk: usize) -> $reg {
+            $load(s.as_ptr().add(chunk * $lanes))
+        }
+
+        #[target_feature(enable = $feature)]
+        pub(in crate::simd) unsafe fn squared_euclidean(p: &[$t], q: &[$t]) -> $t {
+            let n = p.len().min(q.len());
+            let mut acc = $zero();
+            for i in 0..n / $lanes {
+                let d = $sub(load(p, i), load(q, i));
+                acc = $add(acc, $mul(d, d));
+            }
+            let tail = n - n % $lanes;
+            lanes(acc).iter().sum::<$t>()
+                + crate::simd::scalar::$t::squared_euclidean(&p[tail..n], &q[tail..n])
+        }
+
+        #[target_feature(enable = $feature)]
+        pub(in crate::simd) unsafe fn manhattan(p: &[$t], q: &[$t]) -> $t {
+            let n = p.len().min(q.len());
+            let mut acc = $zero();
+            for i in 0..n / $lanes {
+                acc = $add(acc, $abs($sub(load(p, i), load(q, i))));
+            }
+            let tail = n - n % $lanes;
+            lanes(acc).iter().sum::<$t>()
+                + crate::simd::scalar::$t::manhattan(&p[tail..n], &q[tail..n])
+        }
+
+        #[target_feature(enable = $feature)]
+        pub(in crate::simd) unsafe fn chebyshev(p: &[$t], q: &[$t]) -> $t {
+            let n = p.len().min(q.len());
+            let mut acc = $zero();
+            for i in 0..n / $lanes {
+                // MAX returns its second operand when either is NaN, so putting
+                // `acc` second skips NaN differences like `f64::max` does
+                acc = $max($abs($sub(load(p, i), load(q, i))), acc);
+            }
+            let tail = n - n % $lanes;
+            lanes(acc)
+                .iter()
+                .fold(0.0, |max: $t, &v| max.max(v))
+                .max(crate::simd::scalar::$t::chebyshev(&p[tail..n], &q[tail..n]))
+        }
+
+        #[target_feature(enable = $feature)]
+        pub(in crate::simd) unsafe fn inner_product(p: &[$t], q: &[$t]) -> $t {
+            let n = p.len().min(q.len());
+            let mut acc = $zero();
+            for i in 0..n / $lanes {
+                acc = $add(acc, $mul(load(p, i), load(q, i)));
+            }
+            let tail = n - n % $lanes;
+            lanes(acc).iter().sum::<$t>()
+                + crate::simd::scalar::$t::inner_product(&p[tail..n], &q[tail..n])
+        }
+
+        #[target_feature(enable = $feature)]
+        pub(in crate::simd) unsafe fn dot_products(p: &[$t], q: &[$t]) -> ($t, $t, $t) {
+            let n = p.len().min(q.len());
+            let (mut pq, mut pp, mut qq) = ($zero(), $zero(), $zero());
+            for i in 0..n / $lanes {
+                let (p_i, q_i) = (load(p, i), load(q, i));
+                pq = $add(pq, $mul(p_i, q_i));
+                pp = $add(pp, $mul(p_i, p_i));
+                qq = $add(qq, $mul(q_i, q_i));
+            }
+            let tail = n - n % $lanes;
+            let (t_pq, t_pp, t_qq) =
+                crate::simd::scalar::$t::dot_products(&p[tail..n], &q[tail..n]);
+            (
+                lanes(pq).iter().sum::<$t>() + t_pq,
+                lanes(pp).iter().sum::<$t>() + t_pp,
+                lanes(qq).iter().sum::<$t>() + t_qq,
+            )
+        }
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    fn abs_ps(x: __m128) -> __m128 {
+        _mm_andnot_ps(_mm_set1_ps(-0.0), x)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    fn abs_pd(x: __m128d) -> __m128d {
+        _mm_andnot_pd(_mm_set1_pd(-0.0), x)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    fn abs256_ps(x: __m256) -> __m256 {
+        _mm256_andnot_ps(_mm256_set1_ps(-0.0), x)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    fn abs256_pd(x: __m256d) -> __m256d {
+        _mm256_andnot_pd(_mm256_set1_pd(-0.0), x)
+    }
+
+    pub(super) mod sse2 {
+        pub(in crate::simd) mod f32 {
+            use super::super::abs_ps;
+            x86_kernels!(
+                "sse2",
+                f32,
+                4,
+                __m128,
+                _mm_setzero_ps,
+                _mm_loadu_ps,
+                _mm_storeu_ps,
+                _mm_add_ps,
+                _mm_sub_ps,
+                _mm_mul_ps,
+                _mm_max_ps,
+                abs_ps
+            );
+        }
+
+        pub(in crate::simd) mod f64 {
+            use super::super::abs_pd;
+            x86_kernels!(
+                "sse2",
+                f64,
+                2,
+                __m128d,
+                _mm_setzero_pd,
+                _mm_loadu_pd,
+                _mm_storeu_pd,
+                _mm_add_pd,
+                _mm_sub_pd,
+                _mm_mul_pd,
+                _mm_max_pd,
+                abs_pd
+            );
+        }
+    }
+
+    pub(super) mod avx2 {
+        pub(in crate::simd) mod f32 {
+            use super::super::abs256_ps;
+            x86_kernels!(
+                "avx2",
+                f32,
+                8,
+                __m256,
+                _mm256_setzero_ps,
+                _mm256_loadu_ps,
+                _mm256_storeu_ps,
+                _mm256_add_ps,
+                _mm256_sub_ps,
+                _mm256_mul_ps,
+                _mm256_max_ps,
+                abs256_ps
+            );
+        }
+
+        pub(in crate::simd) mod f64 {
+            use super::super::abs256_pd;
+            x86_kernels!(
+                "avx2",
+                f64,
+                4,
+                __m256d,
+                _mm256_setzero_pd,
+                _mm256_loadu_pd,
+                _mm256_storeu_pd,
+                _mm256_add_pd,
+                _mm256_sub_pd,
+                _mm256_mul_pd,
+                _mm256_max_pd,
+                abs256_pd
+            );
+        }
+    }
+
+    pub(super) mod avx512 {
+        pub(in crate::simd) mod f32 {
+            x86_kernels!(
+                "avx512f",
+                f32,
+                16,
+                __m512,
+                _mm512_setzero_ps,
+                _mm512_loadu_ps,
+                _mm512_storeu_ps,
+                _mm512_add_ps,
+                _mm512_sub_ps,
+                _mm512_mul_ps,
+                _mm512_max_ps,
+                _mm512_abs_ps
+            );
+        }
+
+        pub(in crate::simd) mod f64 {
+            x86_kernels!(
+                "avx512f",
+                f64,
+                8,
+                __m512d,
+                _mm512_setzero_pd,
+                _mm512_loadu_pd,
+                _mm512_storeu_pd,
+                _mm512_add_pd,
+                _mm512_sub_pd,
+                _mm512_mul_pd,
+                _mm512_max_pd,
+                _mm512_abs_pd
+            );
+        }
+    }
+}
+
+macro_rules! dispatch {
+    ($t:ident, $kernel:ident, $p:expr, $q:expr) => {{
+        #[cfg(target_arch = "x86_64")]
+        {
+            // SSE2 is part of the x86_64 baseline, so it is always available
+            if is_x86_feature_detected!("avx512f") {
+                unsafe { x86::avx512::$t::$kernel($p, $q) }
+            } else if is_x86_feature_detected!("avx2") {
+                unsafe { x86::avx2::$t::$kernel($p, $q) }
+            } else {
+                unsafe { x86::sse2::$t::$kernel($p, $q) }
+            }
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            scalar::$t::$kernel($p, $q)
+        }
+    }};
+}
+
+macro_rules! simd_float {
+    ($t:ident) => {
+        impl SimdFloat for $t {
+            fn squared_euclidean(p: &[$t], q: &[$t]) -> $t {
+                dispatch!($t, squared_euclidean, p, q)
+            }
+
+            fn manhattan(p: &[$t], q: &[$t]) -> $t {
+                dispatch!($t, manhattan, p, q)
+            }
+
+            fn chebyshev(p: &[$t], q: &[$t]) -> $t {
+                dispatch!($t, chebyshev, p, q)
+            }
+
+            fn inner_product(p: &[$t], q: &[$t]) -> $t {
+                dispatch!($t, inner_product, p, q)
+            }
+
+            fn dot_products(p: &[$t], q: &[$t]) -> ($t, $t, $t) {
+                dispatch!($t, dot_products, p, q)
+            }
+
+            fn sqrt(self) -> $t {
+                <$t>::sqrt(self)
+            }
+        }
+    };
+}
+
+simd_float!(f32);
+simd_float!(f64);
+
+#[cfg(test)]
+mod tests {
+    use crate::{distance, simd, similarity};
+
+    fn vectors(n: usize) -> (Vec<f64>, Vec<f64>) {
+        let p = (0..n).map(|i| ((i * 31) % 97) as f64 / 9.7 - 3.0).collect();
+        let q = (0..n).map(|i| ((i * 17) % 89) as f64 / 8.9 - 4.0).collect();
+        (p, q)
+    }
+
+    // Odd lengths exercise both the vector loop and the scalar tail
+    const SIZES: [usize; 5] = [0, 3, 17, 250, 4099];
+
+    #[test]
+    fn f64_matches_scalar() {
+        for n in SIZES {
+            let (p, q) = vectors(n);
+            assert_relative_eq!(
+                simd::euclidean(&p, &q),
+                distance::euclidean(&p, &q),
+                max_relative = 1e-12
+            );
+            assert_relative_eq!(
+                simd::squared_euclidean(&p, &q),
+                distance::squared_euclidean(&p, &q),
+                max_relative = 1e-12
+            );
+            assert_relative_eq!(
+                simd::manhattan(&p, &q),
+                distance::manhattan(&p, &q),
+                max_relative = 1e-12
+            );
+            assert_eq!(simd::chebyshev(&p, &q), distance::chebyshev(&p, &q));
+            assert_relative_eq!(
+                simd::inner_product(&p, &q),
+                distance::inner_product(&p, &q),
+                max_relative = 1e-12
+            );
+            if n > 0 {
+                assert_relative_eq!(
+                    simd::cosine(&p, &q),
+                    similarity::cosine(&p, &q),
+                    max_relative = 1e-12
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn f32_matches_scalar() {
+        for n in SIZES {
+            let (p, q) = vectors(n);
+            let (p32, q32): (Vec<f32>, Vec<f32>) = (
+                p.iter().map(|&v| v as f32).collect(),
+                q.iter().map(|&v| v as f32).collect(),
+            );
+            assert_relative_eq!(
+                simd::euclidean(&p32, &q32),
+                distance::euclidean(&p32, &q32),
+                max_relative = 1e-5
+            );
+            assert_relative_eq!(
+                simd::manhattan(&p32, &q32),
+                distance::manhattan(&p32, &q32),
+                max_relative = 1e-5
+            );
+            assert_eq!(simd::chebyshev(&p32, &q32), distance::chebyshev(&p32, &q32));
+            if n > 0 {
+                assert_relative_eq!(
+                    simd::cosine(&p32, &q32),
+                    similarity::cosine(&p32, &q32),
+                    max_relative = 1e-5
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn chebyshev_skips_nan() {
+        for n in [3, 17, 250] {
+            let (mut p, q) = vectors(n);
+            // A NaN after the largest difference must not reset the maximum
+            for i in [0, n / 2, n - 1] {
+                p[i] = f64::NAN;
+            }
+            assert_eq!(simd::chebyshev(&p, &q), distance::chebyshev(&p, &q));
+            let (p32, q32): (Vec<f32>, Vec<f32>) = (
+                p.iter().map(|&v| v as f32).collect(),
+                q.iter().map(|&v| v as f32).collect(),
+            );
+            assert_eq!(simd::chebyshev(&p32, &q32), distance::chebyshev(&p32, &q32));
+        }
+    }
+
+    // Runs every kernel the host supports, not only the one picked at runtime
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn x86_kernels_agree() {
+        use super::{scalar, x86};
+
+        for n in SIZES {
+            let (p, q) = vectors(n);
+            let expected = (
+                scalar::f64::squared_euclidean(&p, &q),
+                scalar::f64::manhattan(&p, &q),
+                scalar::f64::chebyshev(&p, &q),
+                scalar::f64::inner_product(&p, &q),
+            );
+            let mut results = vec![unsafe {
+                (
+                    x86::sse2::f64::squared_euclidean(&p, &q),
+                    x86::sse2::f64::manhattan(&p, &q),
+                    x86::sse2::f64::chebyshev(&p, &q),
+                    x86::sse2::f64::inner_product(&p, &q),
+                )
+            }];
+            if is_x86_feature_detected!("avx2") {
+                results.push(unsafe {
+                    (
+                        x86::avx2::f64::squared_euclidean(&p, &q),
+                        x86::avx2::f64::manhattan(&p, &q),
+                        x86::avx2::f64::chebyshev(&p, &q),
+                        x86::avx2::f64::inner_product(&p, &q),
+                    )
+                });
+            }
+            if is_x86_feature_detected!("avx512f") {
+                results.push(unsafe {
+                    (
+                        x86::avx512::f64::squared_euclidean(&p, &q),
+                        x86::avx512::f64::manhattan(&p, &q),
+                        x86::avx512::f64::chebyshev(&p, &q),
+                        x86::avx512::f64::inner_product(&p, &q),
+                    )
+                });
+            }
+
+            for result in results {
+                assert_relative_eq!(result.0, expected.0, max_relative = 1e-12);
+                assert_relative_eq!(result.1, expected.1, max_relative = 1e-12);
+                assert_eq!(result.2, expected.2);
+                assert_relative_eq!(result.3, expected.3, max_relative = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn reference_values() {
+        let p = [0.000, 1.700, 2.350];
+        let q = [0.300, 1.700, 1.001];
+        assert_relative_eq!(simd::euclidean(&p, &q), 1.381955499, epsilon = 1e-9);
+        assert_relative_eq!(simd::cosine(&p, &q), 0.905759279, epsilon = 1e-9);
+    }
+}
@@ -1,125 +1,118 @@
-pub fn cosine<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    let numerator = p
-        .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i * q_i)
-        .sum::<f64>();
-    let denominator = p
-        .iter()
-        .map(|&p| p.into())
-        .map(|p_i| p_i * p_i)
-        .sum::<f64>()
-        .sqrt()
-        * q.iter()
-            .map(|&q| q.into())
-            .map(|q_i| q_i * q_i)
-            .sum::<f64>()
-            .sqrt();
-    numerator / denominator
+use crate::distance::{dot_products, fold_pairs};
+use crate::element::{lit, Element};
+use crate::error::{check_distributions, check_pair, fallible};
+use crate::measure::measures;
+use num_traits::{Float, Zero};
+
+pub fn cosine<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (pq, pp, qq) = dot_products(p, q);
+
+    pq / (pp.sqrt() * qq.sqrt())
 }
 
-pub fn kulczynski<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    1.0 / crate::distance::kulczynski(p, q)
+pub fn kulczynski<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    lit::<T::Float>(1.0) / crate::distance::kulczynski(p, q)
 }
 
-pub fn dice<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    (2.0 * p
-        .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i * q_i)
-        .sum::<f64>())
-        / (p.iter()
-            .map(|&p| p.into())
-            .map(|p_i| p_i * p_i)
-            .sum::<f64>()
-            + q.iter()
-                .map(|&q| q.into())
-                .map(|q_i| q_i * q_i)
-                .sum::<f64>())
+pub fn dice<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (pq, pp, qq) = dot_products(p, q);
+
+    lit::<T::Float>(2.0) * pq / (pp + qq)
 }
 
-pub fn fidelity<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn fidelity<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| (p_i * q_i).sqrt())
         .sum()
 }
 
-pub fn czekanowski<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    (2.0 * p
-        .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i.min(q_i))
-        .sum::<f64>())
-        / p.iter()
-            .map(|&p| p.into())
-            .zip(q.iter().map(|&q| q.into()))
-            .map(|(p_i, q_i)| p_i + q_i)
-            .sum::<f64>()
+pub fn czekanowski<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (min, sum) = fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero()),
+        |(min, sum), (p_i, q_i)| (min + p_i.min(q_i), sum + p_i + q_i),
+    );
+
+    lit::<T::Float>(2.0) * min / sum
 }
 
-pub fn intersection<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn intersection<T: Element>(p: &[T], q: &[T]) -> T::Float {
     p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
+        .map(|&p| p.to_float())
+        .zip(q.iter().map(|&q| q.to_float()))
         .map(|(p_i, q_i)| p_i.min(q_i))
-        .sum::<f64>()
+        .sum::<T::Float>()
 }
 
-pub fn kumar_hassebrook<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    let pq = p
-        .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i * q_i)
-        .sum::<f64>();
-
-    pq / (p
-        .iter()
-        .map(|&p| p.into())
-        .map(|p_i| p_i * p_i)
-        .sum::<f64>()
-        + q.iter()
-            .map(|&q| q.into())
-            .map(|q_i| q_i * q_i)
-            .sum::<f64>()
-        - pq)
+pub fn kumar_hassebrook<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (pq, pp, qq) = dot_products(p, q);
+
+    pq / (pp + qq - pq)
 }
 
-pub fn jaccard<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
+pub fn jaccard<T: Element>(p: &[T], q: &[T]) -> T::Float {
     kumar_hassebrook(p, q)
 }
 
-pub fn motyka<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    p.iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| p_i.min(q_i))
-        .sum::<f64>()
-        / p.iter()
-            .map(|&p| p.into())
-            .zip(q.iter().map(|&q| q.into()))
-            .map(|(p_i, q_i)| p_i + q_i)
-            .sum::<f64>()
+pub fn motyka<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    let (min, sum) = fold_pairs(
+        p,
+        q,
+        (T::Float::zero(), T::Float::zero()),
+        |(min, sum), (p_i, q_i)| (min + p_i.min(q_i), sum + p_i + q_i),
+    );
+
+    min / sum
 }
 
-pub fn squared_chord<T: Into<f64> + Copy>(p: &[T], q: &[T]) -> f64 {
-    2.0 * p
-        .iter()
-        .map(|&p| p.into())
-        .zip(q.iter().map(|&q| q.into()))
-        .map(|(p_i, q_i)| (p_i * q_i).sqrt())
-        .sum::<f64>()
-        - 1.0
+pub fn squared_chord<T: Element>(p: &[T], q: &[T]) -> T::Float {
+    lit::<T::Float>(2.0)
+        * p.iter()
+            .map(|&p| p.to_float())
+            .zip(q.iter().map(|&q| q.to_float()))
+            .map(|(p_i, q_i)| (p_i * q_i).sqrt())
+            .sum::<T::Float>()
+        - lit(1.0)
+}
+
+fallible! {
+    check_pair;
+    try_cosine => cosine,
+    try_kulczynski => kulczynski,
+    try_dice => dice,
+    try_czekanowski => czekanowski,
+    try_intersection => intersection,
+    try_kumar_hassebrook => kumar_hassebrook,
+    try_jaccard => jaccard,
+    try_motyka => motyka,
+}
+
+fallible! {
+    check_distributions;
+    try_fidelity => fidelity,
+    try_squared_chord => squared_chord,
+}
+
+measures! {
+    Similarity, similarity;
+    Cosine => cosine,
+    Kulczynski => kulczynski,
+    Dice => dice,
+    Fidelity => fidelity,
+    Czekanowski => czekanowski,
+    Intersection => intersection,
+    KumarHassebrook => kumar_hassebrook,
+    Jaccard => jaccard,
+    Motyka => motyka,
+    SquaredChord => squared_chord,
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::similarity;
+    use crate::{similarity, Error};
 
     const P: [f64; 3] = [0.000, 1.700, 2.350];
     const Q: [f64; 3] = [0.300, 1.700, 1.001];
@@ -147,4 +140,42 @@ mod tests {
         let result = similarity::kumar_hassebrook(&P, &Q);
         assert_relative_eq!(result, 0.732975296, epsilon = 1e-9);
     }
+
+    #[test]
+    fn try_cosine() {
+        let result = similarity::try_cosine(&P, &Q).unwrap();
+        assert_relative_eq!(result, 0.905759279, epsilon = 1e-9);
+
+        let zero = [0.0; 3];
+        let result = similarity::try_cosine(&P, &zero);
+        assert_eq!(result, Err(Error::DivisionByZero));
+    }
+
+    #[test]
+    fn readme_names_resolve() {
+        let readme = include_str!("../README.md");
+        let table = readme.split("## Similarities Implemented").nth(1).unwrap();
+        let rows = table.lines().skip_while(|l| !l.starts_with('|'));
+        for row in rows.take_while(|l| l.starts_with('|')).skip(2) {
+            let name = row
+                .trim_start_matches('|')
+                .split_whitespace()
+                .next()
+                .unwrap();
+            assert!(
+                similarity::by_name(name).is_some(),
+                "{name} does not resolve"
+            );
+        }
+    }
+
+    #[test]
+    fn by_name() {
+        let measure = similarity::by_name("kumarHassebrook").unwrap();
+        assert_eq!(measure.name(), "kumar_hassebrook");
+        assert_relative_eq!(measure.similarity(&P, &Q), 0.732975296, epsilon = 1e-9);
+
+        assert!(similarity::by_name("squared_chord").is_some());
+        assert!(similarity::by_name("euclidean").is_none());
+    }
 }
@@ -0,0 +1,688 @@
+// Edit distances between strings, compared by Unicode scalar value, or between
+// any sequences of comparable symbols. The `*_bounded` variants give up and
+// return `None` as soon as the distance is known to exceed `max_distance`,
+// which makes them much cheaper when filtering candidates by a threshold.
+// `damerau_levenshtein` has no bounded variant: it fills its whole matrix.
+//
+// The similarities further down (Jaro, q-grams and the token ratios) lie in
+// [0, 1], 1 meaning identical, and two empty inputs are identical.
+
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+
+// Inputs the edit distances accept: `str` (and `String`) as `char`s, and
+// slices, arrays or vectors of any `Eq` symbol. `damerau_levenshtein` and the
+// q-gram similarities also need the symbols to be `Hash`.
+pub trait Symbols {
+    type Item: Eq + Clone;
+
+    fn symbols(&self) -> Cow<'_, [Self::Item]>;
+}
+
+impl Symbols for str {
+    type Item = char;
+
+    fn symbols(&self) -> Cow<'_, [char]> {
+        Cow::Owned(self.chars().collect())
+    }
+}
+
+impl Symbols for String {
+    type Item = char;
+
+    fn symbols(&self) -> Cow<'_, [char]> {
+        self.as_str().symbols()
+    }
+}
+
+impl<T: Eq + Clone> Symbols for [T] {
+    type Item = T;
+
+    fn symbols(&self) -> Cow<'_, [T]> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl<T: Eq + Clone, const N: usize> Symbols for [T; N] {
+    type Item = T;
+
+    fn symbols(&self) -> Cow<'_, [T]> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl<T: Eq + Clone> Symbols for Vec<T> {
+    type Item = T;
+
+    fn symbols(&self) -> Cow<'_, [T]> {
+        Cow::Borrowed(self)
+    }
+}
+
+// Cost of each edit turning the first sequence into the second. An infinite
+// cost forbids that kind of edit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Costs {
+    pub insertion: f64,
+    pub deletion: f64,
+    pub substitution: f64,
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Costs {
+            insertion: 1.0,
+            deletion: 1.0,
+            substitution: 1.0,
+        }
+    }
+}
+
+impl Costs {
+    // NaN or negative costs would break the early exit of the bounded variants
+    fn check(&self) {
+        for cost in [self.insertion, self.deletion, self.substitution] {
+            assert!(cost >= 0.0, "edit costs must be non-negative");
+        }
+    }
+}
+
+// Wagner-Fischer over three rolling rows, the oldest one only serving the
+// adjacent transpositions of OSA, at the given cost. Costs must be
+// non-negative, so the smallest value of the last two rows never decreases and
+// bounds the final distance.
+fn edit_distance<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    costs: &Costs,
+    transposition: Option<f64>,
+    max_distance: f64,
+) -> Option<f64> {
+    let mut before = vec![0.0; b.len() + 1];
+    let mut previous: Vec<f64> = (0..=b.len()).map(|j| j as f64 * costs.insertion).collect();
+    // Not `0 * insertion`, which is NaN for a forbidden insertion
+    previous[0] = 0.0;
+    let mut current = vec![0.0; b.len() + 1];
+    let mut previous_min = 0.0;
+
+    for i in 1..=a.len() {
+        current[0] = i as f64 * costs.deletion;
+        let mut current_min = current[0];
+        for j in 1..=b.len() {
+            let substitution = if a[i - 1] == b[j - 1] {
+                0.0
+            } else {
+                costs.substitution
+            };
+            let mut value = (previous[j - 1] + substitution)
+                .min(previous[j] + costs.deletion)
+                .min(current[j - 1] + costs.insertion);
+            if let Some(transposition) = transposition {
+                if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                    value = value.min(before[j - 2] + transposition);
+                }
+            }
+            current[j] = value;
+            current_min = f64::min(current_min, value);
+        }
+
+        if current_min.min(previous_min) > max_distance {
+            return None;
+        }
+        previous_min = current_min;
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let distance = previous[b.len()];
+    (distance <= max_distance).then_some(distance)
+}
+
+fn unit_distance<S: Symbols + ?Sized>(
+    a: &S,
+    b: &S,
+    transpositions: bool,
+    max_distance: usize,
+) -> Option<usize> {
+    let (a, b) = (a.symbols(), b.symbols());
+    if a.len().abs_diff(b.len()) > max_distance {
+        return None;
+    }
+    edit_distance(
+        &a,
+        &b,
+        &Costs::default(),
+        transpositions.then_some(1.0),
+        max_distance as f64,
+    )
+    .map(|distance| distance as usize)
+}
+
+// Minimum number of insertions, deletions and substitutions
+pub fn levenshtein<S: Symbols + ?Sized>(a: &S, b: &S) -> usize {
+    unit_distance(a, b, false, usize::MAX).unwrap()
+}
+
+pub fn levenshtein_bounded<S: Symbols + ?Sized>(
+    a: &S,
+    b: &S,
+    max_distance: usize,
+) -> Option<usize> {
+    unit_distance(a, b, false, max_distance)
+}
+
+// Optimal string alignment: Levenshtein plus transpositions of adjacent
+// symbols, without editing a substring more than once ("ca" -> "abc" is 3)
+pub fn osa<S: Symbols + ?Sized>(a: &S, b: &S) -> usize {
+    unit_distance(a, b, true, usize::MAX).unwrap()
+}
+
+pub fn osa_bounded<S: Symbols + ?Sized>(a: &S, b: &S, max_distance: usize) -> Option<usize> {
+    unit_distance(a, b, true, max_distance)
+}
+
+// Levenshtein with arbitrary non-negative costs per kind of edit. Panics on a
+// negative or NaN cost.
+pub fn weighted_levenshtein<S: Symbols + ?Sized>(a: &S, b: &S, costs: &Costs) -> f64 {
+    costs.check();
+    edit_distance(&a.symbols(), &b.symbols(), costs, None, f64::INFINITY).unwrap()
+}
+
+pub fn weighted_levenshtein_bounded<S: Symbols + ?Sized>(
+    a: &S,
+    b: &S,
+    costs: &Costs,
+    max_distance: f64,
+) -> Option<f64> {
+    costs.check();
+    edit_distance(&a.symbols(), &b.symbols(), costs, None, max_distance)
+}
+
+// Unrestricted Damerau-Levenshtein (Lowrance-Wagner): transposed symbols may
+// be edited again, so unlike `osa` it satisfies the triangle inequality
+// ("ca" -> "abc" is 2)
+pub fn damerau_levenshtein<S: Symbols + ?Sized>(a: &S, b: &S) -> usize
+where
+    S::Item: Hash,
+{
+    let (a, b) = (a.symbols(), b.symbols());
+    let (rows, columns) = (a.len() + 2, b.len() + 2);
+    let infinity = a.len() + b.len();
+
+    // `d[i + 1][j + 1]` is the distance between `a[..i]` and `b[..j]`, with an
+    // extra border of `infinity`
+    let mut d = vec![0; rows * columns];
+    d[0] = infinity;
+    for i in 0..=a.len() {
+        d[(i + 1) * columns] = infinity;
+        d[(i + 1) * columns + 1] = i;
+    }
+    for j in 0..=b.len() {
+        d[j + 1] = infinity;
+        d[columns + j + 1] = j;
+    }
+
+    // Last row of `a` where each symbol was seen
+    let mut last_row: HashMap<&S::Item, usize> = HashMap::new();
+    for i in 1..=a.len() {
+        let mut last_match_column = 0;
+        for j in 1..=b.len() {
+            let k = last_row.get(&b[j - 1]).copied().unwrap_or(0);
+            let l = last_match_column;
+            let cost = if a[i - 1] == b[j - 1] {
+                last_match_column = j;
+                0
+            } else {
+                1
+            };
+            d[(i + 1) * columns + j + 1] = (d[i * columns + j] + cost)
+                .min(d[(i + 1) * columns + j] + 1)
+                .min(d[i * columns + j + 1] + 1)
+                .min(d[k * columns + l] + (i - k - 1) + 1 + (j - l - 1));
+        }
+        last_row.insert(&a[i - 1], i);
+    }
+
+    d[rows * columns - 1]
+}
+
+fn jaro_symbols<T: PartialEq>(a: &[T], b: &[T]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    // Symbols match when equal and at most `window` positions apart
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    let mut a_matches = Vec::new();
+    for (i, symbol) in a.iter().enumerate() {
+        let (start, end) = (i.saturating_sub(window), (i + window + 1).min(b.len()));
+        if let Some(j) = (start..end).find(|&j| !b_matched[j] && b[j] == *symbol) {
+            b_matched[j] = true;
+            a_matches.push(symbol);
+        }
+    }
+    if a_matches.is_empty() {
+        return 0.0;
+    }
+
+    // Matched symbols that appear in a different order, counted twice
+    let b_matches = b.iter().zip(&b_matched).filter(|(_, &matched)| matched);
+    let half_transpositions = a_matches
+        .iter()
+        .zip(b_matches)
+        .filter(|(&a_i, (b_i, _))| a_i != *b_i)
+        .count();
+
+    let m = a_matches.len() as f64;
+    let t = half_transpositions as f64 / 2.0;
+    (m / a.len() as f64 + m / b.len() as f64 + (m - t) / m) / 3.0
+}
+
+pub fn jaro<S: Symbols + ?Sized>(a: &S, b: &S) -> f64 {
+    jaro_symbols(&a.symbols(), &b.symbols())
+}
+
+// Jaro similarity raised for a common prefix of up to 4 symbols, by
+// `prefix_scale` (0.1 in Winkler's paper, at most 0.25) per symbol
+pub fn jaro_winkler<S: Symbols + ?Sized>(a: &S, b: &S, prefix_scale: f64) -> f64 {
+    assert!(
+        (0.0..=0.25).contains(&prefix_scale),
+        "prefix_scale must lie in [0, 0.25]"
+    );
+    let (a, b) = (a.symbols(), b.symbols());
+    let jaro = jaro_symbols(&a, &b);
+    let prefix = a
+        .iter()
+        .zip(b.iter())
+        .take(4)
+        .take_while(|(a_i, b_i)| a_i == b_i)
+        .count();
+
+    jaro + prefix as f64 * prefix_scale * (1.0 - jaro)
+}
+
+// Number of occurrences of every run of `q` consecutive symbols
+fn qgrams<T: Eq + Hash>(symbols: &[T], q: usize) -> HashMap<&[T], usize> {
+    assert!(q > 0, "q must be positive");
+    let mut counts = HashMap::new();
+    for gram in symbols.windows(q) {
+        *counts.entry(gram).or_insert(0) += 1;
+    }
+    counts
+}
+
+// An input shorter than `q` has no q-grams to compare, so the two inputs score
+// 1 when they are equal and 0 otherwise
+fn without_qgrams<T: PartialEq>(a: &[T], b: &[T], q: usize) -> Option<f64> {
+    (a.len() < q || b.len() < q).then(|| f64::from(u8::from(a == b)))
+}
+
+// Jaccard similarity of the sets of q-grams
+pub fn qgram_jaccard<S: Symbols + ?Sized>(a: &S, b: &S, q: usize) -> f64
+where
+    S::Item: Hash,
+{
+    let (a, b) = (a.symbols(), b.symbols());
+    if let Some(similarity) = without_qgrams(&a, &b, q) {
+        return similarity;
+    }
+    let (a, b) = (qgrams(&a, q), qgrams(&b, q));
+    let common = a.keys().filter(|gram| b.contains_key(*gram)).count();
+
+    common as f64 / (a.len() + b.len() - common) as f64
+}
+
+// Cosine similarity of the q-gram counts
+pub fn qgram_cosine<S: Symbols + ?Sized>(a: &S, b: &S, q: usize) -> f64
+where
+    S::Item: Hash,
+{
+    let (a, b) = (a.symbols(), b.symbols());
+    if let Some(similarity) = without_qgrams(&a, &b, q) {
+        return similarity;
+    }
+    let (a, b) = (qgrams(&a, q), qgrams(&b, q));
+    let dot: usize = a
+        .iter()
+        .filter_map(|(gram, count)| b.get(gram).map(|other| count * other))
+        .sum();
+    let norm =
+        |counts: &HashMap<_, usize>| counts.values().map(|c| (c * c) as f64).sum::<f64>().sqrt();
+
+    dot as f64 / (norm(&a) * norm(&b))
+}
+
+// `1 - (insertions + deletions) / (|a| + |b|)`, the `ratio` of fuzzywuzzy and
+// RapidFuzz scaled to [0, 1]
+pub fn ratio<S: Symbols + ?Sized>(a: &S, b: &S) -> f64 {
+    let (a, b) = (a.symbols(), b.symbols());
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let indel = Costs {
+        substitution: 2.0,
+        ..Costs::default()
+    };
+    let distance = edit_distance(&a, &b, &indel, None, f64::INFINITY).unwrap();
+
+    1.0 - distance / (a.len() + b.len()) as f64
+}
+
+fn join(tokens: impl Iterator<Item = impl AsRef<str>>) -> String {
+    tokens
+        .map(|t| t.as_ref().to_owned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// `ratio` of the whitespace-separated tokens, sorted, so that word order does
+// not matter. Tokens are compared as they are, without case folding.
+pub fn token_sort_ratio(a: &str, b: &str) -> f64 {
+    let sorted = |s: &str| {
+        let mut tokens: Vec<&str> = s.split_whitespace().collect();
+        tokens.sort_unstable();
+        join(tokens.into_iter())
+    };
+    ratio(&sorted(a), &sorted(b))
+}
+
+// Best `ratio` between the common tokens alone and followed by the tokens of
+// either side only, so that a string whose tokens are a subset of the other's
+// scores 1
+pub fn token_set_ratio(a: &str, b: &str) -> f64 {
+    let a: BTreeSet<&str> = a.split_whitespace().collect();
+    let b: BTreeSet<&str> = b.split_whitespace().collect();
+    if a.is_empty() || b.is_empty() {
+        return f64::from(u8::from(a.is_empty() && b.is_empty()));
+    }
+
+    let common = join(a.intersection(&b));
+    let with_common = |only: String| match (common.is_empty(), only.is_empty()) {
+        (_, true) => common.clone(),
+        (true, false) => only,
+        (false, false) => format!("{common} {only}"),
+    };
+    let a_combined = with_common(join(a.difference(&b)));
+    let b_combined = with_common(join(b.difference(&a)));
+
+    ratio(&common, &a_combined)
+        .max(ratio(&common, &b_combined))
+        .max(ratio(&a_combined, &b_combined))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::samples;
+    use crate::string::{self, Costs};
+
+    #[test]
+    fn levenshtein() {
+        assert_eq!(string::levenshtein("kitten", "sitting"), 3);
+        assert_eq!(string::levenshtein("", "abc"), 3);
+        assert_eq!(string::levenshtein("abc", ""), 3);
+        assert_eq!(string::levenshtein("", ""), 0);
+        assert_eq!(string::levenshtein("flaw", "lawn"), 2);
+        // One scalar value, although two bytes in UTF-8
+        assert_eq!(string::levenshtein("café", "cafe"), 1);
+        assert_eq!(
+            string::levenshtein(&"kitten".to_string(), &"sitting".to_string()),
+            3
+        );
+    }
+
+    #[test]
+    fn sequences() {
+        assert_eq!(string::levenshtein(&[1, 2, 3, 4], &[1, 3, 4, 5]), 2);
+        assert_eq!(string::osa(&vec![1, 2, 3], &vec![2, 1, 3]), 1);
+        let words = ["the", "quick", "fox"];
+        assert_eq!(
+            string::damerau_levenshtein(&words[..], &["quick", "the", "fox"][..]),
+            1
+        );
+    }
+
+    #[test]
+    fn unhashable_symbols() {
+        // `Eq` but not `Hash`
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        struct Point(u8, u8);
+
+        let a = [Point(0, 0), Point(1, 1), Point(2, 2)];
+        let b = [Point(1, 1), Point(0, 0), Point(2, 2)];
+        assert_eq!(string::levenshtein(&a, &b), 2);
+        assert_eq!(string::osa(&a, &b), 1);
+        assert_eq!(string::levenshtein_bounded(&a, &b, 1), None);
+        assert_eq!(string::jaro(&a, &a), 1.0);
+    }
+
+    #[test]
+    fn transpositions() {
+        assert_eq!(string::levenshtein("ab", "ba"), 2);
+        assert_eq!(string::osa("ab", "ba"), 1);
+        assert_eq!(string::damerau_levenshtein("ab", "ba"), 1);
+
+        // OSA cannot edit the transposed pair again
+        assert_eq!(string::osa("ca", "abc"), 3);
+        assert_eq!(string::damerau_levenshtein("ca", "abc"), 2);
+
+        assert_eq!(string::osa("a cat", "an act"), 2);
+        assert_eq!(string::damerau_levenshtein("a cat", "an abct"), 3);
+        assert_eq!(string::damerau_levenshtein("", "abc"), 3);
+        assert_eq!(string::damerau_levenshtein("abc", ""), 3);
+    }
+
+    #[test]
+    fn weighted() {
+        let costs = Costs {
+            insertion: 1.0,
+            deletion: 2.0,
+            substitution: 0.5,
+        };
+        assert_eq!(string::weighted_levenshtein("abc", "abd", &costs), 0.5);
+        assert_eq!(string::weighted_levenshtein("abc", "ab", &costs), 2.0);
+        assert_eq!(string::weighted_levenshtein("ab", "abc", &costs), 1.0);
+
+        // Substituting costs as much as deleting and inserting
+        let costs = Costs {
+            substitution: 2.0,
+            ..Costs::default()
+        };
+        assert_eq!(
+            string::weighted_levenshtein("kitten", "sitting", &costs),
+            5.0
+        );
+        assert_eq!(
+            string::weighted_levenshtein("kitten", "sitting", &Costs::default()),
+            3.0
+        );
+    }
+
+    #[test]
+    fn forbidden_edit() {
+        let costs = Costs {
+            insertion: f64::INFINITY,
+            ..Costs::default()
+        };
+        assert_eq!(string::weighted_levenshtein("", "", &costs), 0.0);
+        assert_eq!(string::weighted_levenshtein("ab", "ab", &costs), 0.0);
+        assert_eq!(string::weighted_levenshtein("abc", "ab", &costs), 1.0);
+        assert_eq!(string::weighted_levenshtein("abc", "abd", &costs), 1.0);
+        assert_eq!(
+            string::weighted_levenshtein("ab", "abc", &costs),
+            f64::INFINITY
+        );
+        assert_eq!(
+            string::weighted_levenshtein_bounded("ab", "abc", &costs, 5.0),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "non-negative")]
+    fn negative_cost() {
+        let costs = Costs {
+            deletion: -1.0,
+            ..Costs::default()
+        };
+        string::weighted_levenshtein("abc", "ab", &costs);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-negative")]
+    fn nan_cost() {
+        let costs = Costs {
+            substitution: f64::NAN,
+            ..Costs::default()
+        };
+        string::weighted_levenshtein_bounded("abc", "abd", &costs, 1.0);
+    }
+
+    #[test]
+    fn bounded() {
+        assert_eq!(string::levenshtein_bounded("kitten", "sitting", 2), None);
+        assert_eq!(string::levenshtein_bounded("kitten", "sitting", 3), Some(3));
+        assert_eq!(string::levenshtein_bounded("a", "abcdef", 4), None);
+        assert_eq!(string::osa_bounded("ab", "ba", 1), Some(1));
+        assert_eq!(string::osa_bounded("ab", "ba", 0), None);
+        assert_eq!(
+            string::weighted_levenshtein_bounded("abc", "ab", &Costs::default(), 0.5),
+            None
+        );
+    }
+
+    // Pseudo-random strings over a small alphabet, so that edits overlap
+    fn words(n: usize, seed: u64) -> Vec<String> {
+        samples(n, seed, |rng| {
+            let len = rng.below(9);
+            (0..len)
+                .map(|_| (b'a' + rng.below(4) as u8) as char)
+                .collect()
+        })
+    }
+
+    #[test]
+    fn consistent() {
+        let words = words(60, 7);
+        for a in &words {
+            for b in &words {
+                let (lev, osa, dl) = (
+                    string::levenshtein(a, b),
+                    string::osa(a, b),
+                    string::damerau_levenshtein(a, b),
+                );
+                assert!(dl <= osa && osa <= lev);
+                assert_eq!(lev, string::levenshtein(b, a));
+                for max in 0..6 {
+                    assert_eq!(
+                        string::levenshtein_bounded(a, b, max),
+                        Some(lev).filter(|&d| d <= max)
+                    );
+                    assert_eq!(
+                        string::osa_bounded(a, b, max),
+                        Some(osa).filter(|&d| d <= max)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn jaro() {
+        assert_relative_eq!(
+            string::jaro("MARTHA", "MARHTA"),
+            0.944_444_444,
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            string::jaro("DIXON", "DICKSONX"),
+            0.766_666_667,
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            string::jaro("CRATE", "TRACE"),
+            0.733_333_333,
+            epsilon = 1e-9
+        );
+        assert_eq!(string::jaro("abc", "xyz"), 0.0);
+        assert_eq!(string::jaro("", ""), 1.0);
+        assert_eq!(string::jaro("", "abc"), 0.0);
+        assert_eq!(string::jaro(&[1, 2, 3], &[1, 2, 3]), 1.0);
+    }
+
+    #[test]
+    fn jaro_winkler() {
+        let result = string::jaro_winkler("MARTHA", "MARHTA", 0.1);
+        assert_relative_eq!(result, 0.961_111_111, epsilon = 1e-9);
+        let result = string::jaro_winkler("DIXON", "DICKSONX", 0.1);
+        assert_relative_eq!(result, 0.813_333_333, epsilon = 1e-9);
+        // No common prefix, no boost
+        let result = string::jaro_winkler("CRATE", "TRACE", 0.1);
+        assert_eq!(result, string::jaro("CRATE", "TRACE"));
+        // Only the first 4 symbols of the prefix count
+        let result = string::jaro_winkler("abcdefgh", "abcdefgx", 0.1);
+        assert_relative_eq!(result, 0.95, epsilon = 1e-12);
+        assert_eq!(
+            string::jaro_winkler("MARTHA", "MARHTA", 0.0),
+            string::jaro("MARTHA", "MARHTA")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "prefix_scale")]
+    fn jaro_winkler_scale() {
+        string::jaro_winkler("a", "b", 0.3);
+    }
+
+    #[test]
+    fn qgrams() {
+        assert_relative_eq!(string::qgram_jaccard("night", "nacht", 2), 1.0 / 7.0);
+        assert_relative_eq!(string::qgram_cosine("night", "nacht", 2), 0.25);
+        // Repeated q-grams count once for Jaccard but not for cosine
+        assert_eq!(string::qgram_jaccard("abab", "ab", 2), 0.5);
+        assert_relative_eq!(string::qgram_cosine("abab", "ab", 2), 2.0 / 5.0_f64.sqrt());
+        assert_eq!(string::qgram_jaccard("night", "night", 3), 1.0);
+    }
+
+    #[test]
+    fn shorter_than_q() {
+        for (a, b, expected) in [
+            ("a", "b", 0.0),
+            ("ab", "a", 0.0),
+            ("a", "a", 1.0),
+            ("", "", 1.0),
+        ] {
+            assert_eq!(string::qgram_jaccard(a, b, 2), expected);
+            assert_eq!(string::qgram_cosine(a, b, 2), expected);
+            assert_eq!(string::qgram_jaccard(b, a, 2), expected);
+            assert_eq!(string::qgram_cosine(b, a, 2), expected);
+        }
+    }
+
+    #[test]
+    fn ratios() {
+        let result = string::ratio("this is a test", "this is a test!");
+        assert_relative_eq!(result, 1.0 - 1.0 / 29.0);
+        assert_eq!(string::ratio("", ""), 1.0);
+        assert_eq!(string::ratio("abc", ""), 0.0);
+
+        let (a, b) = ("fuzzy wuzzy was a bear", "wuzzy fuzzy was a bear");
+        assert_eq!(string::token_sort_ratio(a, b), 1.0);
+        let (a, b) = ("fuzzy was a bear", "fuzzy fuzzy was a bear");
+        assert_relative_eq!(string::token_sort_ratio(a, b), 1.0 - 6.0 / 38.0);
+        assert_eq!(string::token_set_ratio(a, b), 1.0);
+
+        let (a, b) = (
+            "mariners vs angels",
+            "los angeles angels of anaheim at seattle mariners",
+        );
+        assert_relative_eq!(string::token_set_ratio(a, b), 0.909_090_909, epsilon = 1e-9);
+        assert_relative_eq!(
+            string::token_sort_ratio(a, b),
+            0.507_462_687,
+            epsilon = 1e-9
+        );
+        assert_eq!(string::token_set_ratio("", "abc"), 0.0);
+        assert_eq!(string::token_set_ratio("  ", ""), 1.0);
+    }
+}
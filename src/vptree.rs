@@ -0,0 +1,250 @@
+use crate::knn::{sort_neighbours, Nearest, Neighbour};
+use crate::measure::Metric;
+use crate::pairwise::rows;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf {
+        start: usize,
+        end: usize,
+    },
+    Vantage {
+        index: usize,
+        radius: f64,
+        inside: usize,
+        outside: usize,
+    },
+}
+
+// Vantage-point tree over the rows of a flat row-major buffer. It only relies
+// on the triangle inequality, hence the `Metric` bound.
+#[derive(Debug, Clone)]
+pub struct VpTree<M> {
+    data: Vec<f64>,
+    dim: usize,
+    metric: M,
+    indices: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+const LEAF_SIZE: usize = 8;
+
+impl<M: Metric> VpTree<M> {
+    pub fn new(data: &[f64], dim: usize, metric: M) -> Self {
+        Self::with_leaf_size(data, dim, metric, LEAF_SIZE)
+    }
+
+    pub fn with_leaf_size(data: &[f64], dim: usize, metric: M, leaf_size: usize) -> Self {
+        assert!(
+            metric.is_metric(),
+            "{} is not a metric with these parameters",
+            metric.name()
+        );
+        let n = rows(data, dim).len();
+        let mut tree = VpTree {
+            data: data.to_vec(),
+            dim,
+            metric,
+            indices: (0..n).collect(),
+            nodes: Vec::new(),
+        };
+        if n > 0 {
+            tree.build(0, n, leaf_size.max(1));
+        }
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    fn row(&self, index: usize) -> &[f64] {
+        &self.data[index * self.dim..(index + 1) * self.dim]
+    }
+
+    fn build(&mut self, start: usize, end: usize, leaf_size: usize) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(Node::Leaf { start, end });
+        if end - start <= leaf_size {
+            return id;
+        }
+
+        // The first row of the range is the vantage point, the others are split
+        // at the median of their distance to it
+        let index = self.indices[start];
+        let mut others: Vec<(f64, usize)> = self.indices[start + 1..end]
+            .iter()
+            .map(|&i| (self.metric.distance(self.row(index), self.row(i)), i))
+            .collect();
+        let median = (others.len() - 1) / 2;
+        others.select_nth_unstable_by(median, |a, b| a.0.total_cmp(&b.0));
+        let radius = others[median].0;
+        for (slot, &(_, i)) in self.indices[start + 1..end].iter_mut().zip(&others) {
+            *slot = i;
+        }
+
+        let mid = start + 2 + median;
+        let inside = self.build(start + 1, mid, leaf_size);
+        let outside = self.build(mid, end, leaf_size);
+        self.nodes[id] = Node::Vantage {
+            index,
+            radius,
+            inside,
+            outside,
+        };
+        id
+    }
+
+    // The `k` nearest rows, closest first, ties broken by the lowest index
+    pub fn knn(&self, query: &[f64], k: usize) -> Vec<Neighbour<f64>> {
+        assert_eq!(query.len(), self.dim, "query length does not match dim");
+        let mut nearest = Nearest::new(k);
+        if k > 0 && !self.is_empty() {
+            self.search_knn(0, query, &mut nearest);
+        }
+        nearest.into_neighbours()
+    }
+
+    fn search_knn(&self, node: usize, query: &[f64], nearest: &mut Nearest<f64>) {
+        match self.nodes[node] {
+            Node::Leaf { start, end } => {
+                for &index in &self.indices[start..end] {
+                    nearest.push(index, self.metric.distance(query, self.row(index)));
+                }
+            }
+            Node::Vantage {
+                index,
+                radius,
+                inside,
+                outside,
+            } => {
+                let d = self.metric.distance(query, self.row(index));
+                nearest.push(index, d);
+
+                // Rows inside are at least `d - radius` away, rows outside at
+                // least `radius - d`. Equal bounds are visited for the ties.
+                let visit = |nearest: &Nearest<f64>, gap: f64| {
+                    nearest.bound().is_none_or(|bound| gap <= bound)
+                };
+                if d < radius {
+                    self.search_knn(inside, query, nearest);
+                    if visit(nearest, radius - d) {
+                        self.search_knn(outside, query, nearest);
+                    }
+                } else {
+                    self.search_knn(outside, query, nearest);
+                    if visit(nearest, d - radius) {
+                        self.search_knn(inside, query, nearest);
+                    }
+                }
+            }
+        }
+    }
+
+    // Every row within `radius` (inclusive), closest first
+    pub fn radius(&self, query: &[f64], radius: f64) -> Vec<Neighbour<f64>> {
+        assert_eq!(query.len(), self.dim, "query length does not match dim");
+        let mut neighbours = Vec::new();
+        if !self.is_empty() {
+            self.search_radius(0, query, radius, &mut neighbours);
+        }
+        sort_neighbours(&mut neighbours);
+        neighbours
+    }
+
+    fn search_radius(&self, node: usize, query: &[f64], r: f64, out: &mut Vec<Neighbour<f64>>) {
+        match self.nodes[node] {
+            Node::Leaf { start, end } => {
+                for &index in &self.indices[start..end] {
+                    let score = self.metric.distance(query, self.row(index));
+                    if score <= r {
+                        out.push(Neighbour { index, score });
+                    }
+                }
+            }
+            Node::Vantage {
+                index,
+                radius,
+                inside,
+                outside,
+            } => {
+                let d = self.metric.distance(query, self.row(index));
+                if d <= r {
+                    out.push(Neighbour { index, score: d });
+                }
+                if d - radius <= r {
+                    self.search_radius(inside, query, r, out);
+                }
+                if radius - d <= r {
+                    self.search_radius(outside, query, r, out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::distance::{Canberra, Clark, Euclidean, Hellinger, Manhattan, Minkowski};
+    use crate::rng::samples;
+    use crate::vptree::VpTree;
+    use crate::{knn, Metric};
+
+    // Coarse positive values so that ties occur and the ratio measures stay
+    // defined
+    fn dataset(n: usize, dim: usize, seed: u64) -> Vec<f64> {
+        samples(n * dim, seed, |rng| (rng.below(20) + 1) as f64 / 4.0)
+    }
+
+    fn matches_brute_force<M: Metric + Copy>(metric: M, r: f64) {
+        for dim in [2, 5, 16] {
+            let data = dataset(300, dim, dim as u64);
+            let queries = dataset(30, dim, 100 + dim as u64);
+            let measure = |p: &[f64], q: &[f64]| metric.distance(p, q);
+
+            let tree = VpTree::with_leaf_size(&data, dim, metric, 3);
+            for query in queries.chunks(dim) {
+                for k in [1, 4, 15] {
+                    assert_eq!(tree.knn(query, k), knn::knn(query, &data, dim, k, measure));
+                }
+                assert_eq!(
+                    tree.radius(query, r),
+                    knn::radius(query, &data, dim, r, measure)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn metrics_match_brute_force() {
+        matches_brute_force(Euclidean, 3.0);
+        matches_brute_force(Manhattan, 5.0);
+        matches_brute_force(Minkowski { d: 3.0 }, 2.5);
+        matches_brute_force(Canberra, 1.5);
+        matches_brute_force(Clark, 0.8);
+        matches_brute_force(Hellinger, 1.5);
+    }
+
+    #[test]
+    fn edge_cases() {
+        let data = dataset(20, 3, 1);
+        let tree = VpTree::new(&data, 3, Euclidean);
+        assert_eq!(tree.len(), 20);
+        assert!(tree.knn(&[1.0, 1.0, 1.0], 0).is_empty());
+        assert_eq!(tree.knn(&[1.0, 1.0, 1.0], 50).len(), 20);
+
+        let empty = VpTree::new(&[], 3, Manhattan);
+        assert!(empty.knn(&[0.0, 0.0, 0.0], 1).is_empty());
+        assert!(empty.radius(&[0.0, 0.0, 0.0], 1.0).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "not a metric")]
+    fn rejects_fractional_minkowski() {
+        VpTree::new(&[0.0, 1.0], 2, Minkowski { d: 0.5 });
+    }
+}
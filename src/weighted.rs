@@ -0,0 +1,253 @@
+// Per-feature weighted versions of the Minkowski-family and ratio distances.
+// Each term is multiplied by its weight, so unit weights give back the
+// measures of `distance`. `chebyshev` ignores features of weight zero, which
+// is the limit of `minkowski` for a growing order.
+
+use num_traits::{Float, Zero};
+
+use crate::element::{lit, Element};
+use crate::error::{check_overflow, check_pair, check_result, Error, Result};
+
+fn weighted_terms<'a, T: Element>(
+    p: &'a [T],
+    q: &'a [T],
+    weights: &'a [T::Float],
+) -> impl Iterator<Item = (T::Float, T::Float, T::Float)> + 'a {
+    p.iter()
+        .zip(q.iter())
+        .zip(weights.iter())
+        .map(|((&p_i, &q_i), &w_i)| (p_i.to_float(), q_i.to_float(), w_i))
+}
+
+pub fn euclidean<T: Element>(p: &[T], q: &[T], weights: &[T::Float]) -> T::Float {
+    weighted_terms(p, q, weights)
+        .map(|(p_i, q_i, w_i)| w_i * (p_i - q_i).powi(2))
+        .sum::<T::Float>()
+        .sqrt()
+}
+
+pub fn manhattan<T: Element>(p: &[T], q: &[T], weights: &[T::Float]) -> T::Float {
+    weighted_terms(p, q, weights)
+        .map(|(p_i, q_i, w_i)| w_i * (p_i - q_i).abs())
+        .sum()
+}
+
+pub fn minkowski<T: Element>(p: &[T], q: &[T], weights: &[T::Float], d: f64) -> T::Float {
+    weighted_terms(p, q, weights)
+        .map(|(p_i, q_i, w_i)| w_i * (p_i - q_i).abs().powf(lit(d)))
+        .sum::<T::Float>()
+        .powf(lit(1.0 / d))
+}
+
+pub fn chebyshev<T: Element>(p: &[T], q: &[T], weights: &[T::Float]) -> T::Float {
+    weighted_terms(p, q, weights)
+        .filter(|&(_, _, w_i)| w_i > T::Float::zero())
+        .map(|(p_i, q_i, _)| (p_i - q_i).abs())
+        .fold(T::Float::zero(), Float::max)
+}
+
+pub fn canberra<T: Element>(p: &[T], q: &[T], weights: &[T::Float]) -> T::Float {
+    weighted_terms(p, q, weights)
+        .map(|(p_i, q_i, w_i)| w_i * (p_i - q_i).abs() / (p_i.abs() + q_i.abs()))
+        .sum()
+}
+
+// Weighted mean of the absolute differences
+pub fn gower<T: Element>(p: &[T], q: &[T], weights: &[T::Float]) -> T::Float {
+    let (sum, total) = weighted_terms(p, q, weights).fold(
+        (T::Float::zero(), T::Float::zero()),
+        |(sum, total), (p_i, q_i, w_i)| (sum + w_i * (p_i - q_i).abs(), total + w_i),
+    );
+
+    sum / total
+}
+
+fn check_weights<T: Element>(p: &[T], q: &[T], weights: &[T::Float]) -> Result<()> {
+    check_pair(p, q)?;
+    if weights.len() != p.len() {
+        return Err(Error::WeightsLengthMismatch {
+            len: p.len(),
+            weights: weights.len(),
+        });
+    }
+    if let Some(index) = weights.iter().position(|w| !w.is_finite()) {
+        return Err(Error::NonFinite { index });
+    }
+    match weights.iter().position(|&w| w < T::Float::zero()) {
+        Some(index) => Err(Error::NegativeWeight { index }),
+        None => Ok(()),
+    }
+}
+
+// Generates the `try_*` counterparts, validating the weights as well. The
+// result is checked with the given checker.
+macro_rules! fallible_weighted {
+    ($result:path; $($name:ident => $measure:ident),* $(,)?) => {
+        $(
+            #[doc = concat!("Checked version of [`", stringify!($measure), "`].")]
+            pub fn $name<T: Element>(p: &[T], q: &[T], weights: &[T::Float]) -> Result<T::Float> {
+                check_weights(p, q, weights)?;
+                $result($measure(p, q, weights))
+            }
+        )*
+    };
+}
+
+// Without a division, only an overflow makes the result non-finite
+fallible_weighted! {
+    check_overflow;
+    try_euclidean => euclidean,
+    try_manhattan => manhattan,
+    try_chebyshev => chebyshev,
+}
+
+fallible_weighted! {
+    check_result;
+    try_canberra => canberra,
+    try_gower => gower,
+}
+
+pub fn try_minkowski<T: Element>(
+    p: &[T],
+    q: &[T],
+    weights: &[T::Float],
+    d: f64,
+) -> Result<T::Float> {
+    check_weights(p, q, weights)?;
+    // Only a positive order keeps the powers away from a zero base
+    if d > 0.0 {
+        check_overflow(minkowski(p, q, weights, d))
+    } else {
+        check_result(minkowski(p, q, weights, d))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{distance, weighted, Error};
+
+    const P: [f64; 3] = [0.000, 1.700, 2.350];
+    const Q: [f64; 3] = [0.300, 1.700, 1.001];
+    const W: [f64; 3] = [0.500, 1.000, 2.000];
+    const UNIT: [f64; 3] = [1.0, 1.0, 1.0];
+
+    #[test]
+    fn unit_weights() {
+        assert_eq!(
+            weighted::euclidean(&P, &Q, &UNIT),
+            distance::euclidean(&P, &Q)
+        );
+        assert_eq!(
+            weighted::manhattan(&P, &Q, &UNIT),
+            distance::manhattan(&P, &Q)
+        );
+        assert_relative_eq!(
+            weighted::minkowski(&P, &Q, &UNIT, 3.0),
+            distance::minkowski(&P, &Q, 3.0),
+            epsilon = 1e-12
+        );
+        assert_eq!(
+            weighted::chebyshev(&P, &Q, &UNIT),
+            distance::chebyshev(&P, &Q)
+        );
+        assert_relative_eq!(
+            weighted::canberra(&[1.0, 2.0, 3.0], &[2.0, 2.0, 1.0], &UNIT),
+            distance::canberra(&[1.0, 2.0, 3.0], &[2.0, 2.0, 1.0]),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            weighted::gower(&P, &Q, &UNIT),
+            distance::gower(&P, &Q),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn euclidean() {
+        let result = weighted::euclidean(&P, &Q, &W);
+        assert_relative_eq!(result, 1.919531714, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn manhattan() {
+        let result = weighted::manhattan(&P, &Q, &W);
+        assert_relative_eq!(result, 2.848, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn minkowski() {
+        let result = weighted::minkowski(&P, &Q, &W, 3.0);
+        assert_relative_eq!(result, 1.701189836, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn chebyshev() {
+        let result = weighted::chebyshev(&P, &Q, &W);
+        assert_relative_eq!(result, 1.349, epsilon = 1e-9);
+        let result = weighted::chebyshev(&P, &Q, &[1.0, 1.0, 0.0]);
+        assert_relative_eq!(result, 0.3, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn canberra() {
+        // P has a zero, so the first term is 0.3 / 0.3
+        let result = weighted::canberra(&P, &Q, &W);
+        assert_relative_eq!(result, 1.305132796, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn gower() {
+        let result = weighted::gower(&P, &Q, &W);
+        assert_relative_eq!(result, 0.813714286, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn f32_weights() {
+        let p = P.map(|v| v as f32);
+        let q = Q.map(|v| v as f32);
+        let result = weighted::euclidean(&p, &q, &W.map(|v| v as f32));
+        assert_relative_eq!(result, 1.919_531_7, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn try_overflow() {
+        let (p, q, w) = ([1e200, 0.0], [-1e200, 0.0], [1.0, 1.0]);
+        assert_eq!(weighted::try_euclidean(&p, &q, &w), Err(Error::Overflow));
+        assert_eq!(
+            weighted::try_minkowski(&p, &q, &w, 3.0),
+            Err(Error::Overflow)
+        );
+        assert_eq!(
+            weighted::try_gower(&P, &Q, &[0.0; 3]),
+            Err(Error::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn try_weights() {
+        assert_eq!(
+            weighted::try_manhattan(&P, &Q, &W),
+            Ok(weighted::manhattan(&P, &Q, &W))
+        );
+        assert_eq!(
+            weighted::try_euclidean(&P, &Q, &[1.0, 1.0]),
+            Err(Error::WeightsLengthMismatch { len: 3, weights: 2 })
+        );
+        assert_eq!(
+            weighted::try_euclidean(&P, &Q[..2], &W),
+            Err(Error::LengthMismatch { p: 3, q: 2 })
+        );
+        assert_eq!(
+            weighted::try_minkowski(&P, &Q, &[1.0, -0.5, 1.0], 3.0),
+            Err(Error::NegativeWeight { index: 1 })
+        );
+        assert_eq!(
+            weighted::try_gower(&P, &Q, &[1.0, 1.0, f64::NAN]),
+            Err(Error::NonFinite { index: 2 })
+        );
+        assert_eq!(
+            weighted::try_gower(&P, &Q, &[0.0, 0.0, 0.0]),
+            Err(Error::DivisionByZero)
+        );
+    }
+}